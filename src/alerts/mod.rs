@@ -0,0 +1,415 @@
+use crate::config::AlertsConfig;
+use raytx::dex::{ArbitrageOpportunity, TokenPrice};
+use raytx::metrics;
+use raytx::mints;
+use raytx::price_fetcher::ScanResult;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+pub mod rules;
+#[cfg(test)]
+mod stand_in;
+pub mod telegram;
+pub mod webhook;
+
+use rules::{Field, PriceHistory, Subject, Target};
+use webhook::Webhook;
+
+/// Name the `[alerts]` thresholds go by in logs, payloads and routing
+pub const THRESHOLD_RULE: &str = "thresholds";
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &str;
+    async fn notify(&self, alert: &Alert) -> Result<()>;
+}
+
+/// An opportunity or price that matched the thresholds or a rule
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub rule: String,
+    pub mint: String,
+    pub message: String,
+    /// Body of json webhooks without a payload template
+    pub body: Value,
+    /// Values available to webhook payload templates as `{name}`
+    pub fields: Vec<(String, Value)>,
+    // Same from cycle to cycle while the match holds
+    key: String,
+}
+
+impl Alert {
+    /// Spells out the direction of the trade
+    pub fn opportunity(rule: &str, opportunity: &ArbitrageOpportunity) -> Self {
+        let ((buy_dex, buy_price), (sell_dex, sell_price)) = opportunity.venues();
+        let token = opportunity.token_name.as_deref().unwrap_or("Unknown");
+
+        let mut message = format!(
+            "{}{} ({}): {:.2}% net spread, buy on {} at {}, sell on {} at {}",
+            rule_prefix(rule),
+            token,
+            opportunity.token_address,
+            opportunity.net_difference_percent,
+            buy_dex,
+            buy_price,
+            sell_dex,
+            sell_price
+        );
+        if let Some(profit_usd) = opportunity.profit_usd {
+            message.push_str(&format!(", est. profit ${:.2}", profit_usd));
+        }
+
+        let fields = vec![
+            ("rule", json!(rule)),
+            ("message", json!(message)),
+            ("mint", json!(opportunity.token_address)),
+            ("token", json!(token)),
+            ("buy_dex", json!(buy_dex)),
+            ("buy_price", json!(buy_price)),
+            ("sell_dex", json!(sell_dex)),
+            ("sell_price", json!(sell_price)),
+            ("quote_mint", json!(opportunity.quote_mint)),
+            ("price_difference_percent", json!(opportunity.price_difference_percent)),
+            ("net_difference_percent", json!(opportunity.net_difference_percent)),
+            ("transfer_fee_bps", json!(opportunity.transfer_fee_bps)),
+            ("profit_usd", json!(opportunity.profit_usd)),
+            ("liquidity_usd", json!(opportunity.liquidity_usd)),
+            ("timestamp", json!(opportunity.timestamp.to_rfc3339())),
+        ];
+
+        Self {
+            rule: rule.to_string(),
+            mint: opportunity.token_address.clone(),
+            body: json!({
+                "type": "arbitrage_opportunity",
+                "rule": rule,
+                "message": message,
+                "buy_dex": buy_dex,
+                "sell_dex": sell_dex,
+                "opportunity": opportunity,
+            }),
+            fields: fields.into_iter().map(|(name, value)| (name.to_string(), value)).collect(),
+            key: format!("{}:{}:{}", opportunity.token_address, buy_dex, sell_dex),
+            message,
+        }
+    }
+
+    /// `changes` are the price change fields the rule reads, by field name
+    pub fn price(rule: &str, price: &TokenPrice, changes: Vec<(String, f64)>) -> Self {
+        let quote = mints::find_by_address(&price.quote_mint).map(|mint| mint.symbol).unwrap_or("quote");
+
+        let mut message = format!(
+            "{}{} on {}: {} {}",
+            rule_prefix(rule),
+            price.token_address,
+            price.dex_name,
+            price.price,
+            quote
+        );
+        if let Some(price_usd) = price.price_usd {
+            message.push_str(&format!(" (${})", price_usd));
+        }
+        for (name, change) in &changes {
+            message.push_str(&format!(", {:+.2}% over {}", change, name.trim_start_matches("price_change_")));
+        }
+
+        let mut fields: Vec<(String, Value)> = vec![
+            ("rule".to_string(), json!(rule)),
+            ("message".to_string(), json!(message)),
+            ("mint".to_string(), json!(price.token_address)),
+            ("dex".to_string(), json!(price.dex_name)),
+            ("price".to_string(), json!(price.price)),
+            ("quote_mint".to_string(), json!(price.quote_mint)),
+            ("price_usd".to_string(), json!(price.price_usd)),
+            ("liquidity_usd".to_string(), json!(price.liquidity_usd)),
+            ("timestamp".to_string(), json!(price.timestamp.to_rfc3339())),
+        ];
+        fields.extend(changes.iter().map(|(name, change)| (name.clone(), json!(change))));
+
+        Self {
+            rule: rule.to_string(),
+            mint: price.token_address.clone(),
+            body: json!({
+                "type": "price",
+                "rule": rule,
+                "message": message,
+                "price": price,
+                "changes": changes.iter().cloned().collect::<HashMap<_, _>>(),
+            }),
+            fields,
+            key: format!("{}:{}", price.token_address, price.dex_name),
+            message,
+        }
+    }
+}
+
+fn rule_prefix(rule: &str) -> String {
+    match rule {
+        THRESHOLD_RULE => String::new(),
+        rule => format!("[{}] ", rule),
+    }
+}
+
+// A match keeps holding for many cycles in a row; it alerts once and not
+// again until it has dropped out. On top of that each token stays quiet for
+// the cooldown after any alert, and a match held back by the cooldown alerts
+// once it's over. Kept per rule.
+#[derive(Default)]
+struct AlertHistory {
+    // Keys that alerted and have matched every cycle since
+    alerted: HashSet<String>,
+    last_alerted: HashMap<String, Instant>,
+}
+
+impl AlertHistory {
+    fn filter(&mut self, alerts: Vec<Alert>, cooldown: Duration, now: Instant) -> Vec<Alert> {
+        let active: HashSet<&str> = alerts.iter().map(|alert| alert.key.as_str()).collect();
+        self.alerted.retain(|key| active.contains(key.as_str()));
+        self.last_alerted.retain(|_, alerted| now.duration_since(*alerted) < cooldown);
+
+        let mut fresh = Vec::new();
+        for alert in alerts {
+            if self.alerted.contains(&alert.key) || self.last_alerted.contains_key(&alert.mint) {
+                continue;
+            }
+            self.alerted.insert(alert.key.clone());
+            self.last_alerted.insert(alert.mint.clone(), now);
+            fresh.push(alert);
+        }
+        fresh
+    }
+}
+
+pub fn create_notifiers(config: &AlertsConfig) -> Result<Vec<Arc<dyn Notifier>>> {
+    config
+        .webhooks
+        .iter()
+        .map(|webhook| Ok(Arc::new(Webhook::new(webhook)?) as Arc<dyn Notifier>))
+        .collect()
+}
+
+/// Outlives config reloads so a reload doesn't repeat the alerts already sent.
+/// Bots run for the whole process and are kept when the webhooks change.
+pub struct Alerter {
+    notifiers: Vec<Arc<dyn Notifier>>,
+    bots: Vec<Arc<dyn Notifier>>,
+    histories: HashMap<String, AlertHistory>,
+    prices: PriceHistory,
+}
+
+impl Alerter {
+    pub fn new(config: &AlertsConfig) -> Result<Self> {
+        Ok(Self {
+            notifiers: create_notifiers(config)?,
+            bots: Vec::new(),
+            histories: HashMap::new(),
+            prices: PriceHistory::default(),
+        })
+    }
+
+    pub fn set_notifiers(&mut self, notifiers: Vec<Arc<dyn Notifier>>) {
+        self.notifiers = notifiers;
+    }
+
+    pub fn add_bot(&mut self, bot: Arc<dyn Notifier>) {
+        self.bots.push(bot);
+    }
+
+    fn fresh(&mut self, rule: &str, alerts: Vec<Alert>, cooldown_secs: u64) -> Vec<Alert> {
+        self.histories
+            .entry(rule.to_string())
+            .or_default()
+            .filter(alerts, Duration::from_secs(cooldown_secs), Instant::now())
+    }
+
+    /// Checks the thresholds and every rule against a cycle, logs the new
+    /// alerts and hands them to their notifiers in the background, so a slow
+    /// webhook never holds up the next scan
+    pub fn process(&mut self, config: &AlertsConfig, scan: &ScanResult) {
+        if !config.enabled {
+            return;
+        }
+
+        // Each alert with the notifiers it goes to, None for all of them
+        let mut routed: Vec<(Alert, Option<&[String]>)> = Vec::new();
+
+        let alerts = scan
+            .opportunities
+            .iter()
+            .filter(|opportunity| config.matches(opportunity))
+            .map(|opportunity| Alert::opportunity(THRESHOLD_RULE, opportunity))
+            .collect();
+        for alert in self.fresh(THRESHOLD_RULE, alerts, config.cooldown_secs) {
+            routed.push((alert, config.notify.as_deref()));
+        }
+
+        if let Some(keep) = config.rules.iter().flat_map(|rule| rule.when.windows()).max() {
+            self.prices.record(&scan.prices, keep);
+        }
+
+        for rule in &config.rules {
+            // Mixed rules are rejected when the config is parsed
+            let alerts = match rule.when.target().unwrap_or(Target::Opportunities) {
+                Target::Opportunities => scan
+                    .opportunities
+                    .iter()
+                    .filter(|opportunity| {
+                        rule.when.matches(&Subject::Opportunity { opportunity, sol_usd: scan.sol_usd })
+                    })
+                    .map(|opportunity| Alert::opportunity(&rule.name, opportunity))
+                    .collect(),
+                Target::Prices => {
+                    let mut windows = rule.when.windows();
+                    windows.sort();
+                    windows.dedup();
+
+                    scan.prices
+                        .iter()
+                        .filter(|price| {
+                            rule.when.matches(&Subject::Price { price, history: &self.prices, sol_usd: scan.sol_usd })
+                        })
+                        .map(|price| {
+                            let changes = windows
+                                .iter()
+                                .filter_map(|window| {
+                                    let change = self.prices.change(price, *window)?;
+                                    Some((Field::PriceChange(*window).to_string(), change))
+                                })
+                                .collect();
+                            Alert::price(&rule.name, price, changes)
+                        })
+                        .collect()
+                }
+            };
+
+            let cooldown_secs = rule.cooldown_secs.unwrap_or(config.cooldown_secs);
+            for alert in self.fresh(&rule.name, alerts, cooldown_secs) {
+                routed.push((alert, Some(&rule.notify)));
+            }
+        }
+
+        for (alert, _) in &routed {
+            warn!(rule = %alert.rule, mint = %alert.mint, alert = %alert.message, "Alert");
+        }
+
+        for notifier in self.notifiers.iter().chain(&self.bots) {
+            let alerts: Vec<Alert> = routed
+                .iter()
+                .filter(|(_, notify)| notify.is_none_or(|names| names.iter().any(|name| name == notifier.name())))
+                .map(|(alert, _)| alert.clone())
+                .collect();
+            if alerts.is_empty() {
+                continue;
+            }
+
+            let notifier = notifier.clone();
+            tokio::spawn(async move {
+                for alert in &alerts {
+                    let result = notifier.notify(alert).await;
+                    metrics::record_alert(notifier.name(), result.is_ok());
+                    if let Err(e) = result {
+                        error!(notifier = notifier.name(), rule = %alert.rule, mint = %alert.mint, error = %format_args!("{:#}", e), "Failed to send alert");
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WebhookConfig;
+    use axum::http::StatusCode;
+    use raytx::dex::LookupErrors;
+    use stand_in::StandIn;
+
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    fn opportunity(mint: &str) -> ArbitrageOpportunity {
+        ArbitrageOpportunity::new(mint.to_string(), None, ("Raydium", 1.0), ("Meteora", 1.1), mints::WSOL.address.to_string())
+    }
+
+    fn alerts(mints: &[&str]) -> Vec<Alert> {
+        mints.iter().map(|mint| Alert::opportunity(THRESHOLD_RULE, &opportunity(mint))).collect()
+    }
+
+    fn alerted_mints(alerts: Vec<Alert>) -> Vec<String> {
+        alerts.into_iter().map(|alert| alert.mint).collect()
+    }
+
+    #[test]
+    fn held_match_alerts_once() {
+        let mut history = AlertHistory::default();
+        let start = Instant::now();
+
+        assert_eq!(alerted_mints(history.filter(alerts(&["A"]), COOLDOWN, start)), ["A"]);
+        for minutes in 1..5 {
+            let now = start + Duration::from_secs(minutes * 60);
+            assert!(history.filter(alerts(&["A"]), COOLDOWN, now).is_empty());
+        }
+    }
+
+    #[test]
+    fn match_returning_after_cooldown_alerts_again() {
+        let mut history = AlertHistory::default();
+        let start = Instant::now();
+
+        history.filter(alerts(&["A"]), COOLDOWN, start);
+        history.filter(alerts(&[]), COOLDOWN, start + Duration::from_secs(10));
+        // Back within the cooldown: quiet, but it alerts once that ends
+        assert!(history.filter(alerts(&["A"]), COOLDOWN, start + Duration::from_secs(20)).is_empty());
+        let after = start + COOLDOWN + Duration::from_secs(1);
+        assert_eq!(alerted_mints(history.filter(alerts(&["A"]), COOLDOWN, after)), ["A"]);
+        assert!(history.filter(alerts(&["A"]), COOLDOWN, after + COOLDOWN * 2).is_empty());
+    }
+
+    #[test]
+    fn cooldown_is_per_token() {
+        let mut history = AlertHistory::default();
+        let start = Instant::now();
+
+        assert_eq!(alerted_mints(history.filter(alerts(&["A", "B"]), COOLDOWN, start)), ["A", "B"]);
+        history.filter(alerts(&[]), COOLDOWN, start + Duration::from_secs(1));
+        let fresh = history.filter(alerts(&["A", "C"]), COOLDOWN, start + Duration::from_secs(2));
+        assert_eq!(alerted_mints(fresh), ["C"]);
+    }
+
+    #[tokio::test]
+    async fn alerter_posts_each_alert_once() {
+        let hook = StandIn::start(|_, _| (StatusCode::OK, Value::Null)).await;
+        let config = AlertsConfig {
+            enabled: true,
+            min_net_difference: 5.0,
+            webhooks: vec![WebhookConfig {
+                name: "hook".to_string(),
+                url: hook.url.clone(),
+                format: Default::default(),
+                payload: None,
+            }],
+            ..AlertsConfig::default()
+        };
+        let mut alerter = Alerter::new(&config).unwrap();
+        let scan = ScanResult {
+            prices: Vec::new(),
+            opportunities: vec![opportunity("A"), opportunity("B")],
+            errors: LookupErrors::default(),
+            sol_usd: None,
+        };
+
+        alerter.process(&config, &scan);
+        alerter.process(&config, &scan);
+        let requests = hook.wait_for(2).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(hook.requests().len(), 2);
+        let mints: Vec<&Value> = requests.iter().map(|(_, body)| &body["opportunity"]["token_address"]).collect();
+        assert_eq!(mints, [&json!("A"), &json!("B")]);
+        assert_eq!(requests[0].1["type"], "arbitrage_opportunity");
+    }
+}
@@ -0,0 +1,689 @@
+use raytx::dex::{ArbitrageOpportunity, TokenPrice};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// What a rule is evaluated against, decided by the fields it uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Opportunities,
+    Prices,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    SpreadPct,
+    GrossSpreadPct,
+    TransferFeeBps,
+    ProfitUsd,
+    BuyDex,
+    SellDex,
+    Token,
+    Dex,
+    Mint,
+    LiquidityUsd,
+    LiquiditySol,
+    Price,
+    PriceUsd,
+    PriceChange(Duration),
+}
+
+impl Field {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name.to_lowercase().as_str() {
+            "spread_pct" => Field::SpreadPct,
+            "gross_spread_pct" => Field::GrossSpreadPct,
+            "transfer_fee_bps" => Field::TransferFeeBps,
+            "profit_usd" => Field::ProfitUsd,
+            "buy_dex" => Field::BuyDex,
+            "sell_dex" => Field::SellDex,
+            "token" => Field::Token,
+            "dex" => Field::Dex,
+            "mint" => Field::Mint,
+            "liquidity_usd" | "min_liquidity_usd" => Field::LiquidityUsd,
+            "liquidity_sol" | "min_liquidity_sol" => Field::LiquiditySol,
+            "price" => Field::Price,
+            "price_usd" => Field::PriceUsd,
+            name => match name.strip_prefix("price_change_") {
+                Some(window) => Field::PriceChange(parse_window(window)?),
+                None => bail!("Unknown field {}", name),
+            },
+        })
+    }
+
+    fn target(&self) -> Option<Target> {
+        match self {
+            Field::SpreadPct
+            | Field::GrossSpreadPct
+            | Field::TransferFeeBps
+            | Field::ProfitUsd
+            | Field::BuyDex
+            | Field::SellDex
+            | Field::Token => Some(Target::Opportunities),
+            Field::Price | Field::PriceUsd | Field::PriceChange(_) => Some(Target::Prices),
+            Field::Dex | Field::Mint | Field::LiquidityUsd | Field::LiquiditySol => None,
+        }
+    }
+
+    // USD figures and SOL conversions of them only exist with usd_view
+    fn needs_usd_view(&self) -> bool {
+        matches!(self, Field::ProfitUsd | Field::LiquidityUsd | Field::LiquiditySol | Field::PriceUsd)
+    }
+
+    fn is_text(&self) -> bool {
+        matches!(self, Field::BuyDex | Field::SellDex | Field::Token | Field::Dex | Field::Mint)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Field::SpreadPct => "spread_pct",
+            Field::GrossSpreadPct => "gross_spread_pct",
+            Field::TransferFeeBps => "transfer_fee_bps",
+            Field::ProfitUsd => "profit_usd",
+            Field::BuyDex => "buy_dex",
+            Field::SellDex => "sell_dex",
+            Field::Token => "token",
+            Field::Dex => "dex",
+            Field::Mint => "mint",
+            Field::LiquidityUsd => "liquidity_usd",
+            Field::LiquiditySol => "liquidity_sol",
+            Field::Price => "price",
+            Field::PriceUsd => "price_usd",
+            Field::PriceChange(window) => return write!(f, "price_change_{}", format_window(*window)),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Price history is kept in memory, and chrono date arithmetic panics long
+// before u64 seconds overflow
+const MAX_WINDOW_SECS: u64 = 365 * 24 * 60 * 60;
+
+// Windows are written as 30s, 5m, 1h or 1d
+fn parse_window(window: &str) -> Result<Duration> {
+    let split = window.char_indices().last().map(|(i, _)| i).unwrap_or_default();
+    let (amount, unit) = window.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| anyhow!("Invalid window {}, expected e.g. 5m", window))?;
+    let unit_seconds: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("Invalid window {}, expected e.g. 5m", window),
+    };
+    let seconds = amount
+        .checked_mul(unit_seconds)
+        .filter(|seconds| *seconds <= MAX_WINDOW_SECS)
+        .ok_or_else(|| anyhow!("Invalid window {}, at most 365d", window))?;
+    if seconds == 0 {
+        bail!("Invalid window {}, must not be zero", window);
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+fn format_window(window: Duration) -> String {
+    let seconds = window.as_secs();
+    match seconds {
+        s if s % (24 * 60 * 60) == 0 => format!("{}d", s / (24 * 60 * 60)),
+        s if s % (60 * 60) == 0 => format!("{}h", s / (60 * 60)),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Number(f64),
+    Text(String),
+}
+
+/// Parsed `when` expression of an alert rule, e.g.
+/// `spread_pct > 2 and liquidity_sol > 50 and dex in [Raydium, Meteora]`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+    Compare { field: Field, op: Op, value: Literal },
+    In { field: Field, values: Vec<Literal> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Text(String),
+    Op(Op),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '[' | ']' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    _ => Token::Comma,
+                });
+            }
+            // Percent signs after numbers are decoration
+            '%' => {
+                chars.next();
+            }
+            '>' | '<' | '=' | '!' => {
+                chars.next();
+                let equals = chars.peek() == Some(&'=');
+                if equals {
+                    chars.next();
+                }
+                tokens.push(Token::Op(match (c, equals) {
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('=', _) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    _ => bail!("Unexpected {}", c),
+                }));
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some(ch) => text.push(ch),
+                        None => bail!("Unterminated string {}{}", c, text),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !(ch.is_alphanumeric() || ch == '_' || ch == '-' || ch == '.') {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(match word.parse::<f64>() {
+                    Ok(number) => Token::Number(number),
+                    Err(_) => Token::Word(word),
+                });
+            }
+            c => bail!("Unexpected {}", c),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            token => Err(anyhow!("Expected {:?}, found {:?}", expected, token)),
+        }
+    }
+
+    fn or(&mut self) -> Result<Condition> {
+        let mut terms = vec![self.and()?];
+        while self.keyword("or") {
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Condition::Or(terms) })
+    }
+
+    fn and(&mut self) -> Result<Condition> {
+        let mut terms = vec![self.unary()?];
+        while self.keyword("and") {
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Condition::And(terms) })
+    }
+
+    fn unary(&mut self) -> Result<Condition> {
+        if self.keyword("not") {
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.next();
+            let condition = self.or()?;
+            self.expect(Token::RParen)?;
+            return Ok(condition);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Condition> {
+        let field = match self.next() {
+            // `price change 5m` reads better than price_change_5m
+            Some(Token::Word(name)) if name.eq_ignore_ascii_case("price") && self.keyword("change") => {
+                match self.next() {
+                    Some(Token::Word(window)) => Field::PriceChange(parse_window(&window)?),
+                    token => bail!("Expected a window after price change, found {:?}", token),
+                }
+            }
+            Some(Token::Word(name)) => Field::parse(&name)?,
+            token => bail!("Expected a field, found {:?}", token),
+        };
+
+        if self.keyword("in") {
+            self.expect(Token::LBracket)?;
+            let mut values = vec![self.literal(&field)?];
+            while self.peek() == Some(&Token::Comma) {
+                self.next();
+                values.push(self.literal(&field)?);
+            }
+            self.expect(Token::RBracket)?;
+            return Ok(Condition::In { field, values });
+        }
+
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            token => bail!("Expected a comparison after {}, found {:?}", field, token),
+        };
+        if field.is_text() && !matches!(op, Op::Eq | Op::Ne) {
+            bail!("{} can only be compared with ==, != or in", field);
+        }
+        let value = self.literal(&field)?;
+        Ok(Condition::Compare { field, op, value })
+    }
+
+    fn literal(&mut self, field: &Field) -> Result<Literal> {
+        match (self.next(), field.is_text()) {
+            (Some(Token::Number(number)), false) => Ok(Literal::Number(number)),
+            (Some(Token::Word(text)), true) | (Some(Token::Text(text)), true) => Ok(Literal::Text(text)),
+            // Mints can look like numbers
+            (Some(Token::Number(number)), true) => Ok(Literal::Text(number.to_string())),
+            (token, _) => Err(anyhow!("Invalid value for {}: {:?}", field, token)),
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser { tokens: tokenize(s)?, position: 0 };
+        let condition = parser.or().map_err(|e| anyhow!("Invalid rule {:?}: {}", s, e))?;
+        if let Some(token) = parser.peek() {
+            bail!("Invalid rule {:?}: unexpected {:?}", s, token);
+        }
+        condition.target().map_err(|e| anyhow!("Invalid rule {:?}: {}", s, e))?;
+        Ok(condition)
+    }
+}
+
+impl Condition {
+    fn fields(&self) -> Vec<&Field> {
+        match self {
+            Condition::And(terms) | Condition::Or(terms) => terms.iter().flat_map(Condition::fields).collect(),
+            Condition::Not(term) => term.fields(),
+            Condition::Compare { field, .. } | Condition::In { field, .. } => vec![field],
+        }
+    }
+
+    /// Rules using only shared fields (dex, mint, liquidity) apply to opportunities
+    pub fn target(&self) -> Result<Target> {
+        let targets: Vec<Target> = self.fields().iter().filter_map(|field| field.target()).collect();
+        if targets.contains(&Target::Opportunities) && targets.contains(&Target::Prices) {
+            bail!("mixes opportunity fields with price fields");
+        }
+        Ok(targets.first().copied().unwrap_or(Target::Opportunities))
+    }
+
+    /// Fields that are never set without usd_view, so the config can reject
+    /// rules that would silently never match
+    pub fn usd_fields(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .fields()
+            .into_iter()
+            .filter(|field| field.needs_usd_view())
+            .map(Field::to_string)
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Price change windows the rule reads, so history is kept long enough
+    pub fn windows(&self) -> Vec<Duration> {
+        self.fields()
+            .into_iter()
+            .filter_map(|field| match field {
+                Field::PriceChange(window) => Some(*window),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// A comparison on a value that isn't known (e.g. a price change before
+    /// enough history, or SOL/USD missing this cycle) is false
+    pub fn matches(&self, subject: &Subject) -> bool {
+        match self {
+            Condition::And(terms) => terms.iter().all(|term| term.matches(subject)),
+            Condition::Or(terms) => terms.iter().any(|term| term.matches(subject)),
+            Condition::Not(term) => !term.matches(subject),
+            Condition::Compare { field, op, value } => match (subject.value(field), value) {
+                (Value::Number(Some(actual)), Literal::Number(expected)) => match op {
+                    Op::Gt => actual > *expected,
+                    Op::Ge => actual >= *expected,
+                    Op::Lt => actual < *expected,
+                    Op::Le => actual <= *expected,
+                    Op::Eq => actual == *expected,
+                    Op::Ne => actual != *expected,
+                },
+                (Value::Texts(actual), Literal::Text(expected)) => {
+                    let found = actual.iter().any(|actual| actual.eq_ignore_ascii_case(expected));
+                    match op {
+                        Op::Ne => !found,
+                        _ => found,
+                    }
+                }
+                _ => false,
+            },
+            // Every venue of an opportunity has to be listed
+            Condition::In { field, values } => match subject.value(field) {
+                Value::Number(Some(actual)) => values.contains(&Literal::Number(actual)),
+                Value::Texts(actual) => !actual.is_empty()
+                    && actual.iter().all(|actual| {
+                        values.iter().any(|value| matches!(value, Literal::Text(text) if text.eq_ignore_ascii_case(actual)))
+                    }),
+                _ => false,
+            },
+        }
+    }
+}
+
+enum Value {
+    Number(Option<f64>),
+    Texts(Vec<String>),
+}
+
+pub enum Subject<'a> {
+    Opportunity {
+        opportunity: &'a ArbitrageOpportunity,
+        sol_usd: Option<f64>,
+    },
+    Price {
+        price: &'a TokenPrice,
+        history: &'a PriceHistory,
+        sol_usd: Option<f64>,
+    },
+}
+
+impl Subject<'_> {
+    fn value(&self, field: &Field) -> Value {
+        match self {
+            Subject::Opportunity { opportunity, sol_usd } => {
+                let ((buy_dex, _), (sell_dex, _)) = opportunity.venues();
+                match field {
+                    Field::SpreadPct => Value::Number(Some(opportunity.net_difference_percent)),
+                    Field::GrossSpreadPct => Value::Number(Some(opportunity.price_difference_percent)),
+                    Field::TransferFeeBps => Value::Number(Some(opportunity.transfer_fee_bps as f64)),
+                    Field::ProfitUsd => Value::Number(opportunity.profit_usd),
+                    Field::BuyDex => Value::Texts(vec![buy_dex.to_string()]),
+                    Field::SellDex => Value::Texts(vec![sell_dex.to_string()]),
+                    Field::Token => Value::Texts(opportunity.token_name.iter().cloned().collect()),
+                    Field::Dex => Value::Texts(vec![buy_dex.to_string(), sell_dex.to_string()]),
+                    Field::Mint => Value::Texts(vec![opportunity.token_address.clone()]),
+                    Field::LiquidityUsd => Value::Number(opportunity.liquidity_usd),
+                    Field::LiquiditySol => Value::Number(to_sol(opportunity.liquidity_usd, *sol_usd)),
+                    Field::Price | Field::PriceUsd | Field::PriceChange(_) => Value::Number(None),
+                }
+            }
+            Subject::Price { price, history, sol_usd } => match field {
+                Field::Dex => Value::Texts(vec![price.dex_name.clone()]),
+                Field::Mint => Value::Texts(vec![price.token_address.clone()]),
+                Field::LiquidityUsd => Value::Number(price.liquidity_usd),
+                Field::LiquiditySol => Value::Number(to_sol(price.liquidity_usd, *sol_usd)),
+                Field::Price => Value::Number(Some(price.price)),
+                Field::PriceUsd => Value::Number(price.price_usd),
+                Field::PriceChange(window) => Value::Number(history.change(price, *window)),
+                _ => Value::Number(None),
+            },
+        }
+    }
+}
+
+fn to_sol(usd: Option<f64>, sol_usd: Option<f64>) -> Option<f64> {
+    match (usd, sol_usd) {
+        (Some(usd), Some(sol_usd)) if sol_usd > 0.0 => Some(usd / sol_usd),
+        _ => None,
+    }
+}
+
+// (timestamp, price) observations, oldest first
+type Observations = VecDeque<(DateTime<Local>, f64)>;
+
+/// Recent prices per mint and venue, for `price_change_<window>` fields
+#[derive(Default)]
+pub struct PriceHistory {
+    prices: HashMap<(String, String), Observations>,
+}
+
+impl PriceHistory {
+    /// Keeps one observation older than `keep` so the longest window still has
+    /// a starting point
+    pub fn record(&mut self, prices: &[TokenPrice], keep: Duration) {
+        for price in prices {
+            let key = (price.token_address.clone(), price.dex_name.clone());
+            self.prices.entry(key).or_default().push_back((price.timestamp, price.price));
+        }
+
+        let cutoff = Local::now() - chrono::Duration::seconds(keep.as_secs() as i64);
+        self.prices.retain(|_, observations| {
+            while observations.len() > 1 && observations[1].0 <= cutoff {
+                observations.pop_front();
+            }
+            observations.back().map(|(timestamp, _)| *timestamp > cutoff).unwrap_or(false)
+        });
+    }
+
+    /// Percent change from the newest observation at least `window` older than `price`
+    pub fn change(&self, price: &TokenPrice, window: Duration) -> Option<f64> {
+        let observations = self.prices.get(&(price.token_address.clone(), price.dex_name.clone()))?;
+        let start = price.timestamp - chrono::Duration::seconds(window.as_secs() as i64);
+        let (_, previous) = observations.iter().rev().find(|(timestamp, _)| *timestamp <= start)?;
+        if *previous <= 0.0 {
+            return None;
+        }
+        Some((price.price - previous) / previous * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WSOL: &str = "So11111111111111111111111111111111111111112";
+
+    fn parse(rule: &str) -> Condition {
+        rule.parse().unwrap()
+    }
+
+    fn compare(field: Field, op: Op, value: f64) -> Condition {
+        Condition::Compare { field, op, value: Literal::Number(value) }
+    }
+
+    // 10% gross spread, buy on Raydium and sell on Meteora
+    fn opportunity() -> ArbitrageOpportunity {
+        let mut opportunity =
+            ArbitrageOpportunity::new(WSOL.to_string(), Some("Wrapped SOL".to_string()), ("Raydium", 1.0), ("Meteora", 1.1), WSOL.to_string());
+        opportunity.liquidity_usd = Some(15_000.0);
+        opportunity
+    }
+
+    fn matches_opportunity(rule: &str, sol_usd: Option<f64>) -> bool {
+        parse(rule).matches(&Subject::Opportunity { opportunity: &opportunity(), sol_usd })
+    }
+
+    #[test]
+    fn tokenizes_operators_strings_and_lists() {
+        let tokens = tokenize("spread_pct>=2.5% and dex != 'Raydium' or mint in [\"a b\", x]").unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("spread_pct".to_string()),
+                Token::Op(Op::Ge),
+                Token::Number(2.5),
+                Token::Word("and".to_string()),
+                Token::Word("dex".to_string()),
+                Token::Op(Op::Ne),
+                Token::Text("Raydium".to_string()),
+                Token::Word("or".to_string()),
+                Token::Word("mint".to_string()),
+                Token::Word("in".to_string()),
+                Token::LBracket,
+                Token::Text("a b".to_string()),
+                Token::Comma,
+                Token::Word("x".to_string()),
+                Token::RBracket,
+            ]
+        );
+        assert!(tokenize("spread_pct > 'open").is_err());
+        assert!(tokenize("spread_pct ~ 2").is_err());
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let spread = compare(Field::SpreadPct, Op::Gt, 1.0);
+        let fee = compare(Field::TransferFeeBps, Op::Lt, 2.0);
+        let profit = compare(Field::ProfitUsd, Op::Ge, 3.0);
+
+        assert_eq!(
+            parse("spread_pct > 1 or transfer_fee_bps < 2 and profit_usd >= 3"),
+            Condition::Or(vec![spread.clone(), Condition::And(vec![fee.clone(), profit.clone()])])
+        );
+        assert_eq!(
+            parse("(spread_pct > 1 or transfer_fee_bps < 2) and profit_usd >= 3"),
+            Condition::And(vec![Condition::Or(vec![spread.clone(), fee]), profit])
+        );
+        assert_eq!(parse("not spread_pct > 1"), Condition::Not(Box::new(spread)));
+        assert!(matches_opportunity("spread_pct > 50 or spread_pct > 5 and not transfer_fee_bps > 0", None));
+        assert!(!matches_opportunity("(spread_pct > 50 or spread_pct > 5) and transfer_fee_bps > 0", None));
+    }
+
+    #[test]
+    fn percent_signs_are_decoration() {
+        assert_eq!(parse("spread_pct > 2%"), parse("spread_pct > 2"));
+        assert_eq!(parse("price change 5m < -3%"), parse("price_change_5m < -3"));
+    }
+
+    #[test]
+    fn in_lists_need_every_venue() {
+        assert!(matches_opportunity("dex in [raydium, METEORA, Orca]", None));
+        assert!(!matches_opportunity("dex in [Raydium, Orca]", None));
+        assert!(matches_opportunity("buy_dex in [Raydium] and sell_dex == meteora", None));
+        assert!(matches_opportunity("mint in ['So11111111111111111111111111111111111111112']", None));
+        assert!(matches_opportunity("transfer_fee_bps in [0, 25]", None));
+        assert!(!matches_opportunity("token in [USDC]", None));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for rule in [
+            "spread > 2",
+            "liquidity_eur > 5",
+            "dex > Raydium",
+            "spread_pct > Raydium",
+            "spread_pct > 2 and",
+            "(spread_pct > 2",
+            "spread_pct > 2 price > 1",
+            "price > 1 and spread_pct > 2",
+            "dex in [Raydium",
+        ] {
+            assert!(rule.parse::<Condition>().is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn parses_windows() {
+        assert_eq!(parse_window("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_window("5m").unwrap(), Duration::from_secs(5 * 60));
+        assert_eq!(parse_window("1h").unwrap(), Duration::from_secs(60 * 60));
+        assert_eq!(parse_window("2d").unwrap(), Duration::from_secs(2 * 24 * 60 * 60));
+
+        assert_eq!(parse_window("365d").unwrap(), Duration::from_secs(MAX_WINDOW_SECS));
+        for window in ["0m", "5", "5w", "m", "", "-5m", "366d", "18446744073709551615d", "9223372036854775808s"] {
+            assert!(parse_window(window).is_err(), "{}", window);
+        }
+
+        for window in ["45s", "90m", "36h", "7d"] {
+            assert_eq!(format_window(parse_window(window).unwrap()), window);
+        }
+        assert_eq!(Field::PriceChange(Duration::from_secs(60 * 60)).to_string(), "price_change_1h");
+    }
+
+    #[test]
+    fn liquidity_sol_converts_at_sol_usd() {
+        assert!(matches_opportunity("liquidity_sol > 99 and liquidity_sol < 101", Some(150.0)));
+        assert!(!matches_opportunity("liquidity_sol > 0", None));
+    }
+
+    #[test]
+    fn lists_usd_fields() {
+        assert_eq!(
+            parse("liquidity_sol > 5 and (profit_usd > 1 or liquidity_sol > 10) and spread_pct > 1").usd_fields(),
+            ["liquidity_sol", "profit_usd"]
+        );
+        assert!(parse("price change 5m > 10 and dex == Orca").usd_fields().is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+// Local HTTP server standing in for webhook receivers and the Telegram Bot
+// API in tests. It records every JSON body posted to it and answers with
+// whatever the responder returns for the request path.
+use axum::extract::State;
+use axum::http::{StatusCode, Uri};
+use axum::Json;
+use axum::Router;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+type Responder = Arc<dyn Fn(&str, &Value) -> (StatusCode, Value) + Send + Sync>;
+
+#[derive(Clone)]
+struct Shared {
+    requests: Arc<Mutex<Vec<(String, Value)>>>,
+    responder: Responder,
+}
+
+pub struct StandIn {
+    pub url: String,
+    requests: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl StandIn {
+    pub async fn start(responder: impl Fn(&str, &Value) -> (StatusCode, Value) + Send + Sync + 'static) -> Self {
+        let shared = Shared {
+            requests: Arc::default(),
+            responder: Arc::new(responder),
+        };
+        let requests = shared.requests.clone();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let router = Router::new().fallback(record).with_state(shared);
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        Self { url, requests }
+    }
+
+    /// Request paths and bodies in arrival order
+    pub fn requests(&self) -> Vec<(String, Value)> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Waits for at least `count` requests, for alerts sent in the background
+    pub async fn wait_for(&self, count: usize) -> Vec<(String, Value)> {
+        for _ in 0..200 {
+            let requests = self.requests();
+            if requests.len() >= count {
+                return requests;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {} requests, got {:?}", count, self.requests());
+    }
+}
+
+async fn record(State(shared): State<Shared>, uri: Uri, Json(body): Json<Value>) -> (StatusCode, Json<Value>) {
+    let path = uri.path().to_string();
+    let (status, response) = (shared.responder)(&path, &body);
+    shared.requests.lock().unwrap().push((path, body));
+    (status, Json(response))
+}
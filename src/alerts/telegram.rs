@@ -0,0 +1,383 @@
+use super::{Alert, Notifier, THRESHOLD_RULE};
+use crate::api::ApiState;
+use crate::config::TelegramConfig;
+use raytx::price_fetcher::PriceFetcher;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// getUpdates holds the request open this long when there's nothing new
+const POLL_TIMEOUT_SECS: u64 = 30;
+const POLL_RETRY_DELAY: Duration = Duration::from_secs(5);
+const DEFAULT_MUTE_MINUTES: u64 = 60;
+const MAX_MUTE_MINUTES: u64 = 7 * 24 * 60;
+const TOP_OPPORTUNITIES: usize = 5;
+
+const HELP: &str = "/price <mint> - latest price on every venue\n\
+/top - best opportunities of the last cycle\n\
+/mute <mint> [minutes] - pause alerts for a token (default 60, at most a week)\n\
+/unmute <mint> - resume alerts for a token";
+
+#[derive(Deserialize)]
+struct Response<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+/// Posts alerts to the configured chats and answers commands from them,
+/// reading the same snapshot the HTTP API serves
+pub struct Telegram {
+    name: String,
+    api_url: String,
+    chat_ids: Vec<i64>,
+    api: ApiState,
+    muted: Mutex<HashMap<String, Instant>>,
+    client: reqwest::Client,
+}
+
+impl Telegram {
+    pub fn new(config: &TelegramConfig, api: ApiState) -> Result<Self> {
+        Ok(Self {
+            name: config.name.clone(),
+            api_url: format!("{}/bot{}", config.api_url.trim_end_matches('/'), config.bot_token),
+            chat_ids: config.chat_ids.clone(),
+            api,
+            muted: Mutex::default(),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    // Errors leave out the URL, which carries the bot token
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value, timeout: Duration) -> Result<T> {
+        let response: Response<T> = self
+            .client
+            .post(format!("{}/{}", self.api_url, method))
+            .json(&params)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)
+            .with_context(|| format!("Telegram {} request failed", method))?
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)
+            .with_context(|| format!("Invalid Telegram {} response", method))?;
+
+        match (response.ok, response.result) {
+            (true, Some(result)) => Ok(result),
+            _ => Err(anyhow!(
+                "Telegram {} failed: {}",
+                method,
+                response.description.unwrap_or_else(|| "no description".to_string())
+            )),
+        }
+    }
+
+    async fn send_message(&self, chat_id: i64, text: &str) -> Result<()> {
+        self.call::<Value>("sendMessage", json!({ "chat_id": chat_id, "text": text }), REQUEST_TIMEOUT)
+            .await
+            .map(|_| ())
+    }
+
+    fn is_muted(&self, mint: &str) -> bool {
+        let mut muted = self.muted.lock().unwrap();
+        let now = Instant::now();
+        muted.retain(|_, until| *until > now);
+        muted.contains_key(mint)
+    }
+
+    /// Long-polls for commands until the process exits
+    pub fn spawn_commands(self: Arc<Self>) {
+        tokio::spawn(async move {
+            info!(notifier = %self.name, "Telegram bot listening for commands");
+            let mut offset = 0;
+
+            loop {
+                let params = json!({
+                    "offset": offset,
+                    "timeout": POLL_TIMEOUT_SECS,
+                    "allowed_updates": ["message"],
+                });
+                let updates: Vec<Update> = match self
+                    .call("getUpdates", params, REQUEST_TIMEOUT + Duration::from_secs(POLL_TIMEOUT_SECS))
+                    .await
+                {
+                    Ok(updates) => updates,
+                    Err(e) => {
+                        warn!(notifier = %self.name, error = %format_args!("{:#}", e), "Failed to poll Telegram");
+                        tokio::time::sleep(POLL_RETRY_DELAY).await;
+                        continue;
+                    }
+                };
+
+                for update in updates {
+                    offset = offset.max(update.update_id + 1);
+                    if let Some(Message { chat, text: Some(text) }) = update.message {
+                        self.handle(chat.id, &text).await;
+                    }
+                }
+            }
+        });
+    }
+
+    async fn handle(&self, chat_id: i64, text: &str) {
+        if !self.chat_ids.contains(&chat_id) {
+            debug!(notifier = %self.name, chat_id, "Ignoring message from an unknown chat");
+            return;
+        }
+
+        let reply = self.reply(text).await;
+        if let Err(e) = self.send_message(chat_id, &reply).await {
+            warn!(notifier = %self.name, chat_id, error = %format_args!("{:#}", e), "Failed to answer Telegram command");
+        }
+    }
+
+    async fn reply(&self, text: &str) -> String {
+        let mut words = text.split_whitespace();
+        // Commands in groups may be addressed as /price@raytx_bot
+        let command = words.next().unwrap_or_default().split('@').next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+
+        match (command, args.as_slice()) {
+            ("/price", [mint]) => self.price(mint).await,
+            ("/top", []) => self.top().await,
+            ("/mute", [mint]) => self.mute(mint, DEFAULT_MUTE_MINUTES),
+            ("/mute", [mint, minutes]) => match minutes.parse() {
+                Ok(minutes) => self.mute(mint, minutes),
+                Err(_) => format!("Invalid number of minutes: {}", minutes),
+            },
+            ("/unmute", [mint]) => match self.muted.lock().unwrap().remove(*mint) {
+                Some(_) => format!("Alerts for {} resumed", mint),
+                None => format!("{} was not muted", mint),
+            },
+            _ => HELP.to_string(),
+        }
+    }
+
+    async fn price(&self, mint: &str) -> String {
+        let snapshot = self.api.snapshot().await;
+        let prices: Vec<String> = snapshot
+            .prices
+            .iter()
+            .filter(|price| price.token_address == mint)
+            .map(PriceFetcher::format_price)
+            .collect();
+
+        match (snapshot.updated_at, prices.is_empty()) {
+            (None, _) => "No completed cycle yet".to_string(),
+            (Some(_), true) => format!("No prices for {} in the last cycle", mint),
+            (Some(_), false) => prices.concat(),
+        }
+    }
+
+    async fn top(&self) -> String {
+        let snapshot = self.api.snapshot().await;
+        let top: Vec<String> = snapshot
+            .opportunities
+            .iter()
+            .take(TOP_OPPORTUNITIES)
+            .map(|opportunity| Alert::opportunity(THRESHOLD_RULE, opportunity).message)
+            .collect();
+
+        match (snapshot.updated_at, top.is_empty()) {
+            (None, _) => "No completed cycle yet".to_string(),
+            (Some(_), true) => "No opportunities in the last cycle".to_string(),
+            (Some(_), false) => top.join("\n"),
+        }
+    }
+
+    fn mute(&self, mint: &str, minutes: u64) -> String {
+        let until = minutes
+            .checked_mul(60)
+            .and_then(|secs| Instant::now().checked_add(Duration::from_secs(secs)))
+            .filter(|_| (1..=MAX_MUTE_MINUTES).contains(&minutes));
+        match until {
+            Some(until) => {
+                self.muted.lock().unwrap().insert(mint.to_string(), until);
+                format!("Alerts for {} muted for {} minutes", mint, minutes)
+            }
+            None => format!("Mute for 1 to {} minutes", MAX_MUTE_MINUTES),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for Telegram {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        if self.is_muted(&alert.mint) {
+            return Ok(());
+        }
+
+        for chat_id in &self.chat_ids {
+            self.send_message(*chat_id, &alert.message).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::stand_in::StandIn;
+    use super::*;
+    use axum::http::StatusCode;
+    use chrono::Local;
+    use raytx::dex::{ArbitrageOpportunity, LookupErrors, TokenPrice};
+    use raytx::price_fetcher::ScanResult;
+
+    const CHAT: i64 = 42;
+    const MINT: &str = "BNZisa39S8KLMDHmmU83FUAJgh2uz8pnzVqWqWyo4EcN";
+    const WSOL: &str = "So11111111111111111111111111111111111111112";
+
+    fn update(update_id: i64, chat_id: i64, text: &str) -> Value {
+        json!({ "update_id": update_id, "message": { "chat": { "id": chat_id }, "text": text } })
+    }
+
+    // Answers the first poll with `updates` and fails later ones, which the
+    // bot retries only after POLL_RETRY_DELAY
+    async fn bot_api(updates: Vec<Value>) -> StandIn {
+        StandIn::start(move |path, body| {
+            if path.ends_with("/sendMessage") {
+                (StatusCode::OK, json!({ "ok": true, "result": {} }))
+            } else if path.ends_with("/getUpdates") && body["offset"] == 0 {
+                (StatusCode::OK, json!({ "ok": true, "result": updates }))
+            } else {
+                (StatusCode::OK, json!({ "ok": false, "description": "no more updates" }))
+            }
+        })
+        .await
+    }
+
+    async fn bot(server: &StandIn, api: ApiState) -> Arc<Telegram> {
+        let config = TelegramConfig {
+            name: "telegram".to_string(),
+            bot_token: "123:secret".to_string(),
+            chat_ids: vec![CHAT],
+            api_url: server.url.clone(),
+        };
+        Arc::new(Telegram::new(&config, api).unwrap())
+    }
+
+    async fn published() -> ApiState {
+        let api = ApiState::new();
+        let scan = ScanResult {
+            prices: vec![TokenPrice {
+                token_address: MINT.to_string(),
+                dex_name: "Orca".to_string(),
+                price: 0.25,
+                quote_mint: WSOL.to_string(),
+                price_usd: None,
+                liquidity_usd: None,
+                timestamp: Local::now(),
+            }],
+            opportunities: vec![ArbitrageOpportunity::new(MINT.to_string(), None, ("Raydium", 0.25), ("Meteora", 0.3), WSOL.to_string())],
+            errors: LookupErrors::default(),
+            sol_usd: None,
+        };
+        api.publish(&scan, 60).await;
+        api
+    }
+
+    fn replies(requests: &[(String, Value)]) -> Vec<String> {
+        requests
+            .iter()
+            .filter(|(path, _)| path.ends_with("/sendMessage"))
+            .map(|(_, body)| {
+                assert_eq!(body["chat_id"], CHAT);
+                body["text"].as_str().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn answers_commands_from_known_chats() {
+        let server = bot_api(vec![
+            update(1, CHAT, &format!("/price@raytx_bot {}", MINT)),
+            update(2, CHAT, "/top"),
+            update(3, 7, "/top"),
+            update(4, CHAT, &format!("/mute {} 30", MINT)),
+        ])
+        .await;
+        let telegram = bot(&server, published().await).await;
+
+        telegram.clone().spawn_commands();
+        let requests = server.wait_for(4).await;
+        let replies = replies(&requests);
+
+        assert!(requests[0].0.starts_with("/bot123:secret/getUpdates"), "{:?}", requests[0]);
+        assert_eq!(replies.len(), 3, "{:?}", replies);
+        assert!(replies[0].contains(MINT) && replies[0].contains("Orca"), "{}", replies[0]);
+        assert!(replies[1].contains("net spread"), "{}", replies[1]);
+        assert_eq!(replies[2], format!("Alerts for {} muted for 30 minutes", MINT));
+        assert!(telegram.is_muted(MINT));
+    }
+
+    #[tokio::test]
+    async fn rejects_mute_durations_out_of_range() {
+        let server = bot_api(Vec::new()).await;
+        let telegram = bot(&server, ApiState::new()).await;
+        let rejected = format!("Mute for 1 to {} minutes", MAX_MUTE_MINUTES);
+
+        assert_eq!(telegram.reply(&format!("/mute {} 0", MINT)).await, rejected);
+        assert_eq!(telegram.reply(&format!("/mute {} {}", MINT, MAX_MUTE_MINUTES + 1)).await, rejected);
+        assert_eq!(telegram.reply(&format!("/mute {} {}", MINT, u64::MAX)).await, rejected);
+        assert_eq!(telegram.reply(&format!("/mute {} soon", MINT)).await, "Invalid number of minutes: soon");
+        assert!(!telegram.is_muted(MINT));
+
+        let accepted = telegram.reply(&format!("/mute {} {}", MINT, MAX_MUTE_MINUTES)).await;
+        assert_eq!(accepted, format!("Alerts for {} muted for {} minutes", MINT, MAX_MUTE_MINUTES));
+    }
+
+    #[tokio::test]
+    async fn muted_tokens_get_no_alerts() {
+        let server = bot_api(Vec::new()).await;
+        let telegram = bot(&server, ApiState::new()).await;
+        let opportunity = ArbitrageOpportunity::new(MINT.to_string(), None, ("Raydium", 0.25), ("Meteora", 0.3), WSOL.to_string());
+        let alert = Alert::opportunity(THRESHOLD_RULE, &opportunity);
+
+        telegram.reply(&format!("/mute {}", MINT)).await;
+        telegram.notify(&alert).await.unwrap();
+        telegram.reply(&format!("/unmute {}", MINT)).await;
+        telegram.notify(&alert).await.unwrap();
+
+        assert_eq!(replies(&server.requests()), [alert.message]);
+    }
+
+    #[tokio::test]
+    async fn answers_before_the_first_cycle() {
+        let server = bot_api(Vec::new()).await;
+        let telegram = bot(&server, ApiState::new()).await;
+
+        assert_eq!(telegram.reply("/top").await, "No completed cycle yet");
+        assert_eq!(telegram.reply(&format!("/price {}", MINT)).await, "No completed cycle yet");
+        assert_eq!(telegram.reply("/help").await, HELP);
+    }
+}
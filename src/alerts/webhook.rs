@@ -0,0 +1,185 @@
+use super::{Alert, Notifier};
+use crate::config::WebhookConfig;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default body of each format; `payload` in the config replaces it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WebhookFormat {
+    #[default]
+    Json,
+    Slack,
+    Discord,
+}
+
+impl FromStr for WebhookFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(WebhookFormat::Json),
+            "slack" => Ok(WebhookFormat::Slack),
+            "discord" => Ok(WebhookFormat::Discord),
+            _ => Err(anyhow!("Unknown webhook format: {}. Expected json, slack or discord", s)),
+        }
+    }
+}
+
+pub struct Webhook {
+    name: String,
+    url: String,
+    format: WebhookFormat,
+    payload: Option<Value>,
+    client: reqwest::Client,
+}
+
+impl Webhook {
+    pub fn new(config: &WebhookConfig) -> Result<Self> {
+        Ok(Self {
+            name: config.name.clone(),
+            url: config.url.clone(),
+            format: config.format,
+            payload: config.payload.clone(),
+            client: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?,
+        })
+    }
+
+    fn payload(&self, alert: &Alert) -> Value {
+        match (&self.payload, self.format) {
+            (Some(template), _) => render(template, &alert.fields),
+            (None, WebhookFormat::Json) => alert.body.clone(),
+            (None, WebhookFormat::Slack) => json!({ "text": alert.message }),
+            (None, WebhookFormat::Discord) => json!({ "content": alert.message }),
+        }
+    }
+}
+
+// Replaces `{name}` placeholders in every string of the template. A string
+// that is only a placeholder takes the field's JSON value, so numbers stay
+// numbers.
+fn render(template: &Value, fields: &[(String, Value)]) -> Value {
+    match template {
+        Value::String(text) => {
+            if let Some((_, value)) = fields.iter().find(|(name, _)| *text == format!("{{{}}}", name)) {
+                return value.clone();
+            }
+
+            let mut text = text.clone();
+            for (name, value) in fields {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Null => String::new(),
+                    value => value.to_string(),
+                };
+                text = text.replace(&format!("{{{}}}", name), &value);
+            }
+            Value::String(text)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| render(item, fields)).collect()),
+        Value::Object(entries) => Value::Object(
+            entries.iter().map(|(key, value)| (key.clone(), render(value, fields))).collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+#[async_trait]
+impl Notifier for Webhook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Errors leave out the URL, which carries the webhook's secret
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(&self.payload(alert))
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)
+            .context("Webhook request failed")?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)
+            .context("Webhook rejected the alert")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::stand_in::StandIn;
+    use axum::http::StatusCode;
+    use raytx::dex::ArbitrageOpportunity;
+
+    fn alert() -> Alert {
+        let opportunity = ArbitrageOpportunity::new(
+            "So11111111111111111111111111111111111111112".to_string(),
+            Some("Wrapped SOL".to_string()),
+            ("Raydium", 1.0),
+            ("Meteora", 1.1),
+            "EPjFWdd5AufqSSqeM2qNksxvRUbxz6hmw8FeDh8iw6zQ".to_string(),
+        );
+        Alert::opportunity("wide", &opportunity)
+    }
+
+    fn webhook(url: &str, format: WebhookFormat, payload: Option<Value>) -> Webhook {
+        Webhook::new(&WebhookConfig {
+            name: "hook".to_string(),
+            url: url.to_string(),
+            format,
+            payload,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn posts_each_format() {
+        let server = StandIn::start(|_, _| (StatusCode::OK, Value::Null)).await;
+        let alert = alert();
+
+        webhook(&format!("{}/json", server.url), WebhookFormat::Json, None).notify(&alert).await.unwrap();
+        webhook(&format!("{}/slack", server.url), WebhookFormat::Slack, None).notify(&alert).await.unwrap();
+        webhook(&format!("{}/discord", server.url), WebhookFormat::Discord, None).notify(&alert).await.unwrap();
+        let requests = server.requests();
+
+        assert_eq!(requests[0].0, "/json");
+        assert_eq!(requests[0].1, alert.body);
+        assert_eq!(requests[1], ("/slack".to_string(), json!({ "text": alert.message })));
+        assert_eq!(requests[2], ("/discord".to_string(), json!({ "content": alert.message })));
+    }
+
+    #[tokio::test]
+    async fn renders_payload_templates() {
+        let server = StandIn::start(|_, _| (StatusCode::OK, Value::Null)).await;
+        let payload = json!({
+            "summary": "{token} buy {buy_dex} sell {sell_dex}",
+            "spread": "{net_difference_percent}",
+            "tags": ["{rule}", "{profit_usd}"],
+        });
+
+        webhook(&server.url, WebhookFormat::Json, Some(payload)).notify(&alert()).await.unwrap();
+        let body = &server.requests()[0].1;
+
+        assert_eq!(body["summary"], "Wrapped SOL buy Raydium sell Meteora");
+        assert!(body["spread"].is_f64());
+        assert_eq!(body["tags"], json!(["wide", null]));
+    }
+
+    #[tokio::test]
+    async fn rejected_alert_is_an_error_without_the_url() {
+        let server = StandIn::start(|_, _| (StatusCode::INTERNAL_SERVER_ERROR, Value::Null)).await;
+        let url = format!("{}/secret-token", server.url);
+
+        let error = webhook(&url, WebhookFormat::Slack, None).notify(&alert()).await.unwrap_err();
+        let message = format!("{:#}", error);
+
+        assert!(message.contains("500"), "{}", message);
+        assert!(!message.contains("secret-token"), "{}", message);
+    }
+}
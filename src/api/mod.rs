@@ -0,0 +1,351 @@
+pub mod ws;
+
+use raytx::dex::{ArbitrageOpportunity, TokenPrice};
+use raytx::metrics;
+use raytx::price_fetcher::ScanResult;
+use anyhow::{Context, Result};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{error, info};
+
+// Cycles kept for WebSocket clients that fall behind before they skip ahead
+const UPDATE_BUFFER: usize = 16;
+
+// Results of the latest completed watch cycle, replaced as a whole after each scan
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub prices: Vec<TokenPrice>,
+    pub opportunities: Vec<ArbitrageOpportunity>,
+    pub updated_at: Option<DateTime<Local>>,
+    pub cycles: u64,
+    pub update_interval_secs: u64,
+}
+
+// Shared between the watch loop, which publishes every cycle, and the server
+#[derive(Clone)]
+pub struct ApiState {
+    snapshot: Arc<RwLock<Arc<Snapshot>>>,
+    updates: broadcast::Sender<Arc<Snapshot>>,
+}
+
+impl ApiState {
+    pub fn new() -> Self {
+        Self {
+            snapshot: Arc::default(),
+            updates: broadcast::channel(UPDATE_BUFFER).0,
+        }
+    }
+
+    pub async fn snapshot(&self) -> Arc<Snapshot> {
+        self.snapshot.read().await.clone()
+    }
+
+    pub async fn publish(&self, scan: &ScanResult, update_interval_secs: u64) {
+        let mut snapshot = self.snapshot.write().await;
+        let update = Arc::new(Snapshot {
+            prices: scan.prices.clone(),
+            opportunities: scan.opportunities.clone(),
+            updated_at: Some(Local::now()),
+            cycles: snapshot.cycles + 1,
+            update_interval_secs,
+        });
+        *snapshot = update.clone();
+
+        // Sending only fails when no WebSocket client is connected
+        let _ = self.updates.send(update);
+    }
+}
+
+#[derive(Serialize)]
+struct Pool<'a> {
+    dex_name: &'a str,
+    quote_mint: &'a str,
+    price: f64,
+    price_usd: Option<f64>,
+    liquidity_usd: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct OpportunitiesQuery {
+    min_pct: Option<f64>,
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+// The 400 response for a path that isn't a mint address
+fn invalid_mint(mint: &str) -> Option<Response> {
+    Pubkey::from_str(mint)
+        .err()
+        .map(|e| error(StatusCode::BAD_REQUEST, format!("Invalid mint {}: {}", mint, e)))
+}
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/prices/{mint}", get(prices))
+        .route("/pools/{mint}", get(pools))
+        .route("/opportunities", get(opportunities))
+        .route("/ws", get(ws::upgrade))
+        .route("/metrics", get(metrics))
+        .with_state(state)
+}
+
+pub async fn serve(listen: &str, state: ApiState) -> Result<()> {
+    let listener = TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to bind the API to {}", listen))?;
+    info!(address = %listener.local_addr()?, "API listening");
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router(state)).await {
+            error!(error = %e, "API server stopped");
+        }
+    });
+    Ok(())
+}
+
+// 503 until the first cycle completes and once three intervals pass without one
+async fn health(State(state): State<ApiState>) -> Response {
+    let snapshot = state.snapshot().await;
+
+    let age_secs = snapshot.updated_at.map(|updated_at| (Local::now() - updated_at).num_seconds());
+    let status = match age_secs {
+        None => "starting",
+        Some(age_secs) if age_secs as u64 > snapshot.update_interval_secs.saturating_mul(3) => "stale",
+        Some(_) => "ok",
+    };
+    let code = if status == "ok" { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (code, Json(json!({
+        "status": status,
+        "cycles": snapshot.cycles,
+        "updated_at": snapshot.updated_at,
+        "age_secs": age_secs,
+    })))
+        .into_response()
+}
+
+async fn prices(State(state): State<ApiState>, Path(mint): Path<String>) -> Response {
+    if let Some(response) = invalid_mint(&mint) {
+        return response;
+    }
+
+    let snapshot = state.snapshot().await;
+    let prices: Vec<&TokenPrice> = snapshot.prices.iter().filter(|price| price.token_address == mint).collect();
+    if prices.is_empty() {
+        return error(StatusCode::NOT_FOUND, format!("No prices for {} in the latest cycle", mint));
+    }
+
+    Json(json!({
+        "mint": mint,
+        "updated_at": snapshot.updated_at,
+        "prices": prices,
+    }))
+    .into_response()
+}
+
+// DEX venues only, deepest first
+async fn pools(State(state): State<ApiState>, Path(mint): Path<String>) -> Response {
+    if let Some(response) = invalid_mint(&mint) {
+        return response;
+    }
+
+    let snapshot = state.snapshot().await;
+    let mut pools: Vec<Pool> = snapshot
+        .prices
+        .iter()
+        .filter(|price| price.token_address == mint && price.dex_name != "Oracle")
+        .map(|price| Pool {
+            dex_name: &price.dex_name,
+            quote_mint: &price.quote_mint,
+            price: price.price,
+            price_usd: price.price_usd,
+            liquidity_usd: price.liquidity_usd,
+        })
+        .collect();
+    if pools.is_empty() {
+        return error(StatusCode::NOT_FOUND, format!("No pools for {} in the latest cycle", mint));
+    }
+    pools.sort_by(|a, b| b.liquidity_usd.unwrap_or(0.0).total_cmp(&a.liquidity_usd.unwrap_or(0.0)));
+
+    Json(json!({
+        "mint": mint,
+        "updated_at": snapshot.updated_at,
+        "pools": pools,
+    }))
+    .into_response()
+}
+
+async fn opportunities(State(state): State<ApiState>, Query(query): Query<OpportunitiesQuery>) -> Response {
+    let snapshot = state.snapshot().await;
+    let min_pct = query.min_pct.unwrap_or(f64::MIN);
+    let opportunities: Vec<&ArbitrageOpportunity> = snapshot
+        .opportunities
+        .iter()
+        .filter(|opportunity| opportunity.net_difference_percent >= min_pct)
+        .collect();
+
+    Json(json!({
+        "updated_at": snapshot.updated_at,
+        "opportunities": opportunities,
+    }))
+    .into_response()
+}
+
+async fn metrics() -> Response {
+    match metrics::render() {
+        Ok(body) => ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to render metrics: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use chrono::Duration;
+    use raytx::dex::LookupErrors;
+    use raytx::mints;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn price(dex_name: &str, liquidity_usd: Option<f64>) -> TokenPrice {
+        TokenPrice {
+            token_address: mints::MSOL.address.to_string(),
+            dex_name: dex_name.to_string(),
+            price: 1.1,
+            quote_mint: mints::WSOL.address.to_string(),
+            price_usd: None,
+            liquidity_usd,
+            timestamp: Local::now(),
+        }
+    }
+
+    fn opportunity(sell_price: f64) -> ArbitrageOpportunity {
+        ArbitrageOpportunity::new(
+            mints::MSOL.address.to_string(),
+            None,
+            ("Raydium", 1.0),
+            ("Meteora", sell_price),
+            mints::WSOL.address.to_string(),
+        )
+    }
+
+    fn scan(prices: Vec<TokenPrice>, opportunities: Vec<ArbitrageOpportunity>) -> ScanResult {
+        ScanResult {
+            prices,
+            opportunities,
+            errors: LookupErrors::default(),
+            sol_usd: None,
+        }
+    }
+
+    async fn get(state: &ApiState, uri: &str) -> (StatusCode, Value) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn health_is_starting_until_the_first_cycle() {
+        let state = ApiState::new();
+
+        let (status, body) = get(&state, "/health").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "starting");
+
+        state.publish(&scan(Vec::new(), Vec::new()), 60).await;
+        let (status, body) = get(&state, "/health").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["cycles"], 1);
+    }
+
+    #[tokio::test]
+    async fn health_is_stale_after_three_missed_intervals() {
+        let state = ApiState::new();
+        state.publish(&scan(Vec::new(), Vec::new()), 60).await;
+        {
+            let mut snapshot = state.snapshot.write().await;
+            let mut stale = (**snapshot).clone();
+            stale.updated_at = Some(Local::now() - Duration::seconds(181));
+            *snapshot = Arc::new(stale);
+        }
+
+        let (status, body) = get(&state, "/health").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "stale");
+    }
+
+    #[tokio::test]
+    async fn rejects_an_invalid_mint() {
+        let state = ApiState::new();
+
+        for uri in ["/prices/not-a-mint", "/pools/not-a-mint"] {
+            let (status, body) = get(&state, uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert!(body["error"].as_str().unwrap().contains("Invalid mint not-a-mint"), "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn pools_leave_out_the_oracle_and_list_deepest_first() {
+        let state = ApiState::new();
+        let prices = vec![
+            price("Raydium", Some(10_000.0)),
+            price("Oracle", None),
+            price("Orca", None),
+            price("Meteora", Some(50_000.0)),
+        ];
+        state.publish(&scan(prices, Vec::new()), 60).await;
+
+        let (status, body) = get(&state, &format!("/pools/{}", mints::MSOL.address)).await;
+        assert_eq!(status, StatusCode::OK);
+        let dexes: Vec<&str> = body["pools"].as_array().unwrap().iter().map(|pool| pool["dex_name"].as_str().unwrap()).collect();
+        assert_eq!(dexes, ["Meteora", "Raydium", "Orca"]);
+
+        // The oracle is still a price, just not a pool
+        let (_, body) = get(&state, &format!("/prices/{}", mints::MSOL.address)).await;
+        assert_eq!(body["prices"].as_array().unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn oracle_only_mint_has_no_pools() {
+        let state = ApiState::new();
+        state.publish(&scan(vec![price("Oracle", None)], Vec::new()), 60).await;
+
+        let (status, _) = get(&state, &format!("/pools/{}", mints::MSOL.address)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn opportunities_below_min_pct_are_filtered_out() {
+        let state = ApiState::new();
+        state.publish(&scan(Vec::new(), vec![opportunity(1.02), opportunity(1.08)]), 60).await;
+
+        let (_, body) = get(&state, "/opportunities").await;
+        assert_eq!(body["opportunities"].as_array().unwrap().len(), 2);
+
+        let (status, body) = get(&state, "/opportunities?min_pct=5").await;
+        assert_eq!(status, StatusCode::OK);
+        let opportunities = body["opportunities"].as_array().unwrap();
+        assert_eq!(opportunities.len(), 1);
+        assert_eq!(opportunities[0]["sell_price"], 1.08);
+    }
+}
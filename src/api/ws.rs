@@ -0,0 +1,343 @@
+use super::{ApiState, Snapshot};
+use raytx::dex::{ArbitrageOpportunity, TokenPrice};
+use anyhow::{anyhow, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use futures::{Sink, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::timeout;
+use tracing::debug;
+
+// A client that can't take a message within this long is disconnected
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Subscribing again to the same stream replaces its filter. Empty lists match
+// everything.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    SubscribePrices {
+        #[serde(default)]
+        mints: Vec<String>,
+        #[serde(default)]
+        dexes: Vec<String>,
+    },
+    SubscribeOpportunities {
+        min_pct: Option<f64>,
+        #[serde(default)]
+        dexes: Vec<String>,
+    },
+    UnsubscribePrices,
+    UnsubscribeOpportunities,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    Prices { cycle: u64, prices: Vec<&'a TokenPrice> },
+    Opportunities { cycle: u64, opportunities: Vec<&'a ArbitrageOpportunity> },
+    // The client fell behind and `skipped` cycles were dropped
+    Lagged { skipped: u64 },
+    Error { message: String },
+}
+
+struct PriceFilter {
+    mints: HashSet<String>,
+    dexes: HashSet<String>,
+}
+
+struct OpportunityFilter {
+    min_pct: f64,
+    dexes: HashSet<String>,
+}
+
+fn lowercase(dexes: Vec<String>) -> HashSet<String> {
+    dexes.into_iter().map(|dex| dex.to_lowercase()).collect()
+}
+
+impl PriceFilter {
+    fn matches(&self, price: &TokenPrice) -> bool {
+        (self.mints.is_empty() || self.mints.contains(&price.token_address))
+            && (self.dexes.is_empty() || self.dexes.contains(&price.dex_name.to_lowercase()))
+    }
+}
+
+impl OpportunityFilter {
+    fn matches(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        opportunity.net_difference_percent >= self.min_pct
+            && (self.dexes.is_empty()
+                || [&opportunity.buy_venue, &opportunity.sell_venue]
+                    .iter()
+                    .all(|venue| self.dexes.contains(&venue.to_lowercase())))
+    }
+}
+
+// What one client asked for, None for a stream it isn't subscribed to
+#[derive(Default)]
+struct Subscriptions {
+    prices: Option<PriceFilter>,
+    opportunities: Option<OpportunityFilter>,
+}
+
+impl Subscriptions {
+    fn apply(&mut self, message: ClientMessage) {
+        match message {
+            ClientMessage::SubscribePrices { mints, dexes } => {
+                self.prices = Some(PriceFilter { mints: mints.into_iter().collect(), dexes: lowercase(dexes) });
+            }
+            ClientMessage::SubscribeOpportunities { min_pct, dexes } => {
+                self.opportunities = Some(OpportunityFilter {
+                    min_pct: min_pct.unwrap_or(f64::MIN),
+                    dexes: lowercase(dexes),
+                });
+            }
+            ClientMessage::UnsubscribePrices => self.prices = None,
+            ClientMessage::UnsubscribeOpportunities => self.opportunities = None,
+        }
+    }
+}
+
+pub async fn upgrade(State(state): State<ApiState>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| handle(socket, state))
+}
+
+// The client's half of the socket, or a channel in tests
+async fn send<S>(sender: &mut S, message: &ServerMessage<'_>) -> Result<()>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    let text = serde_json::to_string(message)?;
+    timeout(SEND_TIMEOUT, sender.send(Message::Text(text.into())))
+        .await
+        .map_err(|_| anyhow!("client did not read within {:?}", SEND_TIMEOUT))??;
+    Ok(())
+}
+
+async fn send_update<S>(sender: &mut S, snapshot: &Snapshot, subscriptions: &Subscriptions) -> Result<()>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    if let Some(filter) = &subscriptions.prices {
+        let prices: Vec<&TokenPrice> = snapshot.prices.iter().filter(|price| filter.matches(price)).collect();
+        if !prices.is_empty() {
+            send(sender, &ServerMessage::Prices { cycle: snapshot.cycles, prices }).await?;
+        }
+    }
+
+    if let Some(filter) = &subscriptions.opportunities {
+        let opportunities: Vec<&ArbitrageOpportunity> =
+            snapshot.opportunities.iter().filter(|opportunity| filter.matches(opportunity)).collect();
+        if !opportunities.is_empty() {
+            send(sender, &ServerMessage::Opportunities { cycle: snapshot.cycles, opportunities }).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// A cycle read from the broadcast channel, or the count dropped because the
+// client lagged past the buffer
+async fn send_received<S>(
+    sender: &mut S,
+    received: Result<Arc<Snapshot>, RecvError>,
+    subscriptions: &Subscriptions,
+) -> Result<()>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    match received {
+        Ok(snapshot) => send_update(sender, &snapshot, subscriptions).await,
+        Err(RecvError::Lagged(skipped)) => send(sender, &ServerMessage::Lagged { skipped }).await,
+        Err(RecvError::Closed) => Err(anyhow!("the watch loop stopped publishing")),
+    }
+}
+
+// The scanner never waits on clients: each one reads cycles from its own
+// broadcast receiver, skips ahead when it lags past the buffer, and is
+// dropped when a single send stalls
+async fn handle(socket: WebSocket, state: ApiState) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut updates = state.updates.subscribe();
+    let mut subscriptions = Subscriptions::default();
+
+    loop {
+        let result = tokio::select! {
+            message = receiver.next() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(text.as_str()) {
+                    Ok(message) => {
+                        subscriptions.apply(message);
+                        Ok(())
+                    }
+                    Err(e) => send(&mut sender, &ServerMessage::Error { message: format!("Invalid message: {}", e) }).await,
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum
+                Some(Ok(_)) => Ok(()),
+            },
+            received = updates.recv() => send_received(&mut sender, received, &subscriptions).await,
+        };
+
+        if let Err(e) = result {
+            debug!(error = %format_args!("{:#}", e), "Closing WebSocket client");
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::router;
+    use futures::channel::mpsc;
+    use raytx::dex::LookupErrors;
+    use raytx::mints;
+    use raytx::price_fetcher::ScanResult;
+    use chrono::Local;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+    use tokio::sync::broadcast;
+    use tokio_tungstenite::tungstenite;
+
+    fn price(dex_name: &str) -> TokenPrice {
+        TokenPrice {
+            token_address: mints::MSOL.address.to_string(),
+            dex_name: dex_name.to_string(),
+            price: 1.1,
+            quote_mint: mints::WSOL.address.to_string(),
+            price_usd: None,
+            liquidity_usd: None,
+            timestamp: Local::now(),
+        }
+    }
+
+    fn opportunity(buy_venue: &str, sell_venue: &str) -> ArbitrageOpportunity {
+        ArbitrageOpportunity::new(
+            mints::MSOL.address.to_string(),
+            None,
+            (buy_venue, 1.0),
+            (sell_venue, 1.1),
+            mints::WSOL.address.to_string(),
+        )
+    }
+
+    fn subscribe(subscriptions: &mut Subscriptions, message: Value) {
+        subscriptions.apply(serde_json::from_value(message).unwrap());
+    }
+
+    #[test]
+    fn price_filter_matches_dexes_in_any_case() {
+        let filter = PriceFilter {
+            mints: HashSet::new(),
+            dexes: lowercase(vec!["RAYDIUM".to_string()]),
+        };
+
+        assert!(filter.matches(&price("Raydium")));
+        assert!(!filter.matches(&price("Orca")));
+    }
+
+    #[test]
+    fn price_filter_matches_listed_mints() {
+        let filter = PriceFilter {
+            mints: HashSet::from([mints::JITOSOL.address.to_string()]),
+            dexes: HashSet::new(),
+        };
+
+        assert!(!filter.matches(&price("Raydium")));
+    }
+
+    #[test]
+    fn opportunity_filter_needs_both_venues() {
+        let filter = OpportunityFilter {
+            min_pct: f64::MIN,
+            dexes: lowercase(vec!["raydium".to_string(), "Meteora".to_string()]),
+        };
+
+        assert!(filter.matches(&opportunity("Raydium", "Meteora")));
+        assert!(filter.matches(&opportunity("Meteora", "Raydium")));
+        assert!(!filter.matches(&opportunity("Raydium", "Orca")));
+        assert!(!filter.matches(&opportunity("Orca", "Meteora")));
+    }
+
+    #[test]
+    fn opportunity_filter_applies_min_pct() {
+        let filter = OpportunityFilter {
+            min_pct: 15.0,
+            dexes: HashSet::new(),
+        };
+
+        assert!(!filter.matches(&opportunity("Raydium", "Meteora")));
+    }
+
+    #[test]
+    fn subscribing_again_replaces_the_filter() {
+        let mut subscriptions = Subscriptions::default();
+
+        subscribe(&mut subscriptions, json!({ "type": "subscribe_prices", "mints": [mints::MSOL.address] }));
+        subscribe(&mut subscriptions, json!({ "type": "subscribe_prices", "dexes": ["Orca"] }));
+        let prices = subscriptions.prices.as_ref().unwrap();
+        assert!(prices.mints.is_empty());
+        assert_eq!(prices.dexes, HashSet::from(["orca".to_string()]));
+
+        subscribe(&mut subscriptions, json!({ "type": "subscribe_opportunities", "min_pct": 2.0 }));
+        subscribe(&mut subscriptions, json!({ "type": "subscribe_opportunities" }));
+        assert_eq!(subscriptions.opportunities.as_ref().unwrap().min_pct, f64::MIN);
+
+        subscribe(&mut subscriptions, json!({ "type": "unsubscribe_prices" }));
+        assert!(subscriptions.prices.is_none());
+        assert!(subscriptions.opportunities.is_some());
+    }
+
+    #[tokio::test]
+    async fn lagging_client_is_told_how_many_cycles_it_missed() {
+        let (updates, mut receiver) = broadcast::channel(1);
+        for _ in 0..3 {
+            updates.send(Arc::new(Snapshot::default())).unwrap();
+        }
+        let (mut sender, mut sent) = mpsc::unbounded();
+
+        send_received(&mut sender, receiver.recv().await, &Subscriptions::default()).await.unwrap();
+
+        let Some(Message::Text(text)) = sent.next().await else { panic!("expected a text message") };
+        let message: Value = serde_json::from_str(text.as_str()).unwrap();
+        assert_eq!(message, json!({ "type": "lagged", "skipped": 2 }));
+    }
+
+    #[tokio::test]
+    async fn pushes_subscribed_prices_each_cycle() {
+        let state = ApiState::new();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        let router = router(state.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let (mut client, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let subscribe = json!({ "type": "subscribe_prices", "dexes": ["raydium"] });
+        client.send(tungstenite::Message::text(subscribe.to_string())).await.unwrap();
+        // The server reads in order, so once this is answered the subscription is in place
+        client.send(tungstenite::Message::text("{}")).await.unwrap();
+        let reply: Value = serde_json::from_str(client.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(reply["type"], "error");
+
+        let scan = ScanResult {
+            prices: vec![price("Raydium"), price("Orca")],
+            opportunities: Vec::new(),
+            errors: LookupErrors::default(),
+            sol_usd: None,
+        };
+        state.publish(&scan, 60).await;
+
+        let pushed: Value = serde_json::from_str(client.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(pushed["type"], "prices");
+        assert_eq!(pushed["cycle"], 1);
+        let dexes: Vec<&Value> = pushed["prices"].as_array().unwrap().iter().map(|price| &price["dex_name"]).collect();
+        assert_eq!(dexes, [&json!("Raydium")]);
+    }
+}
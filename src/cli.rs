@@ -0,0 +1,291 @@
+use raytx::cluster::Cluster;
+use crate::config::Config;
+use raytx::dex::oracle::OracleFeed;
+use raytx::logging::LogFormat;
+use raytx::output::{OutputFormat, Rolling};
+use raytx::quote::QuoteAsset;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use solana_sdk::pubkey::Pubkey;
+use std::path::PathBuf;
+
+// Flags and their environment variables override individual keys of the config
+// file, which in turn overrides the built-in defaults
+#[derive(Parser)]
+#[command(name = "raytx", version, about = "Multi-DEX price and arbitrage scanner for Solana")]
+pub struct Cli {
+    #[arg(long, env = "RAYTX_CONFIG", global = true, help = "TOML config file [default: raytx.toml if present]")]
+    pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub dex: DexArgs,
+
+    #[arg(long, value_enum, default_value_t = Format::Text, global = true, help = "Output format on stdout")]
+    pub format: Format,
+
+    #[arg(long, value_name = "FILE", global = true, help = "Save the accounts price, pool or scan reads to a JSON fixture")]
+    pub record_fixture: Option<PathBuf>,
+
+    #[arg(long, value_name = "FILE", global = true, help = "Read accounts from a recorded fixture instead of the RPC node")]
+    pub replay_fixture: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "Price a token on every DEX once")]
+    Price {
+        #[arg(help = "Token mint address")]
+        mint: String,
+    },
+    #[command(about = "Decode and print a supported pool account")]
+    Pool {
+        #[arg(help = "Pool account address")]
+        address: Pubkey,
+    },
+    #[command(about = "Run one arbitrage pass and exit")]
+    Scan(ScanArgs),
+    #[command(about = "Scan for arbitrage in a loop (the default)")]
+    Watch(Box<WatchArgs>),
+}
+
+#[derive(Args)]
+pub struct DexArgs {
+    #[arg(long, env = "CLUSTER", global = true, help = "Address presets: mainnet, devnet or localnet")]
+    pub cluster: Option<Cluster>,
+
+    #[arg(long, env = "RPC_URL", global = true, help = "Overrides the cluster's public endpoint")]
+    pub rpc_url: Option<String>,
+
+    #[arg(long, env = "RAYDIUM_PROGRAM_ID", global = true)]
+    pub raydium_program_id: Option<String>,
+
+    #[arg(long, env = "METEORA_PROGRAM_ID", global = true)]
+    pub meteora_program_id: Option<String>,
+
+    #[arg(long, env = "ORCA_PROGRAM_ID", global = true)]
+    pub orca_program_id: Option<String>,
+
+    #[arg(long, env = "ENABLED_DEXES", value_delimiter = ',', global = true, help = "Comma-separated DEX names, e.g. raydium,meteora,orca")]
+    pub enabled_dexes: Option<Vec<String>>,
+
+    #[arg(long, env = "TOKEN_LIST_URL", global = true, help = "Jupiter-style token list, empty to scan the watchlist only")]
+    pub token_list_url: Option<String>,
+
+    #[arg(long, env = "WATCHLIST", value_delimiter = ',', global = true, help = "Comma-separated mints scanned every cycle")]
+    pub watchlist: Option<Vec<String>>,
+
+    #[arg(long, env = "QUOTE_ASSET", global = true, help = "Quote prices in SOL or USD")]
+    pub quote_asset: Option<QuoteAsset>,
+
+    #[arg(long, env = "USD_VIEW", num_args = 0..=1, default_missing_value = "true", global = true, help = "Add USD price, liquidity and profit to outputs")]
+    pub usd_view: Option<bool>,
+
+    #[arg(long, env = "USD_REFERENCE_POOL", global = true, help = "Raydium SOL/USDC pool used for SOL/USD (empty to disable)")]
+    pub usd_reference_pool: Option<String>,
+
+    #[arg(long, env = "USD_FALLBACK_ORACLE", global = true, help = "Pyth SOL/USD account used when the reference pool fails (empty to disable)")]
+    pub usd_fallback_oracle: Option<String>,
+
+    #[arg(long, env = "TRADE_SIZE_USD", global = true)]
+    pub trade_size_usd: Option<f64>,
+
+    #[arg(long, env = "ORACLE_FEEDS", global = true, help = "Comma-separated <mint>:<pyth|switchboard>:<account>")]
+    pub oracle_feeds: Option<String>,
+
+    #[arg(long, env = "ORACLE_MAX_STALENESS_SLOTS", global = true)]
+    pub oracle_max_staleness_slots: Option<u64>,
+
+    #[arg(long, env = "LOG_LEVEL", global = true, help = "Log filter, e.g. info or info,raytx::dex=debug")]
+    pub log_level: Option<String>,
+
+    #[arg(long, env = "LOG_FORMAT", global = true, help = "Log format on stderr: text or json")]
+    pub log_format: Option<LogFormat>,
+}
+
+#[derive(Args)]
+pub struct ScanArgs {
+    #[arg(long, env = "MIN_PRICE_DIFFERENCE", help = "Minimum net spread in percent")]
+    pub min_price_difference: Option<f64>,
+
+    #[arg(long, env = "MAX_ORACLE_DEVIATION", help = "Oracle deviation in percent that gets reported")]
+    pub max_oracle_deviation: Option<f64>,
+}
+
+#[derive(Parser)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    #[arg(long, env = "UPDATE_INTERVAL", help = "Seconds between scans")]
+    pub update_interval: Option<u64>,
+
+    #[arg(long, env = "OUTPUT_FORMATS", value_delimiter = ',')]
+    pub output_formats: Option<Vec<OutputFormat>>,
+
+    #[arg(long, env = "DATA_DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    #[arg(long, env = "OUTPUT_ROLLING")]
+    pub output_rolling: Option<Rolling>,
+
+    #[arg(long, env = "POSTGRES_URL")]
+    pub postgres_url: Option<String>,
+
+    #[arg(long, env = "POSTGRES_TIMESCALE", num_args = 0..=1, default_missing_value = "true")]
+    pub postgres_timescale: Option<bool>,
+
+    #[arg(long, env = "SCANNER_ID")]
+    pub scanner_id: Option<String>,
+
+    #[arg(long, env = "LOG_MAX_FILE_SIZE_MB", help = "0 disables size rotation")]
+    pub log_max_file_size_mb: Option<u64>,
+
+    #[arg(long, env = "LOG_COMPRESS", num_args = 0..=1, default_missing_value = "true")]
+    pub log_compress: Option<bool>,
+
+    #[arg(long, env = "LOG_MAX_AGE_DAYS", help = "0 keeps rotated files forever")]
+    pub log_max_age_days: Option<u64>,
+
+    #[arg(long, env = "LOG_MAX_TOTAL_SIZE_MB", help = "0 disables the size cap")]
+    pub log_max_total_size_mb: Option<u64>,
+
+    #[arg(long, env = "API_ENABLED", num_args = 0..=1, default_missing_value = "true", help = "Serve the latest cycle over HTTP")]
+    pub api: Option<bool>,
+
+    #[arg(long, env = "API_LISTEN", help = "API address as <ip>:<port>")]
+    pub api_listen: Option<String>,
+
+    #[arg(long, env = "TELEGRAM_BOT_TOKEN", hide_env_values = true, help = "Enables the Telegram bot")]
+    pub telegram_bot_token: Option<String>,
+
+    #[arg(long, env = "TELEGRAM_CHAT_IDS", value_delimiter = ',', help = "Comma-separated chats that get alerts and may send commands")]
+    pub telegram_chat_ids: Option<Vec<i64>>,
+
+    #[arg(long, env = "TELEGRAM_API_URL", help = "Bot API server [default: https://api.telegram.org]")]
+    pub telegram_api_url: Option<String>,
+}
+
+fn set<T>(key: &mut T, value: &Option<T>)
+where
+    T: Clone,
+{
+    if let Some(value) = value {
+        *key = value.clone();
+    }
+}
+
+impl Command {
+    pub fn apply(&self, config: &mut Config) {
+        match self {
+            Command::Scan(args) => args.apply(config),
+            Command::Watch(args) => args.apply(config),
+            Command::Price { .. } | Command::Pool { .. } => {}
+        }
+    }
+}
+
+impl DexArgs {
+    pub fn apply(&self, config: &mut Config) -> anyhow::Result<()> {
+        set(&mut config.rpc.cluster, &self.cluster);
+        if let Some(rpc_url) = &self.rpc_url {
+            config.rpc.url = Some(rpc_url.clone()).filter(|url| !url.is_empty());
+        }
+        for (name, program_id) in [
+            ("raydium", &self.raydium_program_id),
+            ("meteora", &self.meteora_program_id),
+            ("orca", &self.orca_program_id),
+        ] {
+            if program_id.is_some() {
+                config.dexes.get_mut(name).program_id = program_id.clone();
+            }
+        }
+        // Names without a config section get one with defaults; unknown
+        // names fail when the fetcher is built
+        if let Some(enabled_dexes) = &self.enabled_dexes {
+            let enabled_dexes: Vec<String> = enabled_dexes
+                .iter()
+                .map(|dex| dex.trim().to_lowercase())
+                .filter(|dex| !dex.is_empty())
+                .collect();
+            for dex in &enabled_dexes {
+                config.dexes.get_mut(dex);
+            }
+            let names: Vec<String> = config.dexes.iter().map(|(name, _)| name.to_string()).collect();
+            for name in names {
+                config.dexes.get_mut(&name).enabled = enabled_dexes.contains(&name);
+            }
+        }
+        set(&mut config.tokens.source_url, &self.token_list_url);
+        if let Some(watchlist) = &self.watchlist {
+            config.tokens.watchlist = watchlist
+                .iter()
+                .map(|mint| mint.trim().to_string())
+                .filter(|mint| !mint.is_empty())
+                .collect();
+        }
+        set(&mut config.quote.asset, &self.quote_asset);
+        set(&mut config.quote.usd_view, &self.usd_view);
+        if self.usd_reference_pool.is_some() {
+            config.quote.usd_reference_pool = self.usd_reference_pool.clone();
+        }
+        if self.usd_fallback_oracle.is_some() {
+            config.quote.usd_fallback_oracle = self.usd_fallback_oracle.clone();
+        }
+        set(&mut config.quote.trade_size_usd, &self.trade_size_usd);
+        if let Some(oracle_feeds) = &self.oracle_feeds {
+            let feeds = oracle_feeds
+                .split(',')
+                .filter(|feed| !feed.trim().is_empty())
+                .map(|feed| feed.parse::<OracleFeed>())
+                .collect::<anyhow::Result<Vec<_>>>()
+                .map_err(|e| e.context("Failed to parse ORACLE_FEEDS"))?;
+            config.oracle.feeds = Some(feeds);
+        }
+        set(&mut config.oracle.max_staleness_slots, &self.oracle_max_staleness_slots);
+        set(&mut config.log.level, &self.log_level);
+        set(&mut config.log.format, &self.log_format);
+        Ok(())
+    }
+}
+
+impl ScanArgs {
+    pub fn apply(&self, config: &mut Config) {
+        set(&mut config.thresholds.min_price_difference, &self.min_price_difference);
+        set(&mut config.thresholds.max_oracle_deviation, &self.max_oracle_deviation);
+    }
+}
+
+impl WatchArgs {
+    pub fn apply(&self, config: &mut Config) {
+        self.scan.apply(config);
+        set(&mut config.thresholds.update_interval_secs, &self.update_interval);
+        set(&mut config.output.formats, &self.output_formats);
+        set(&mut config.output.data_dir, &self.data_dir);
+        set(&mut config.output.rolling, &self.output_rolling);
+        if let Some(postgres_url) = &self.postgres_url {
+            config.output.postgres_url = Some(postgres_url.clone()).filter(|url| !url.is_empty());
+        }
+        set(&mut config.output.postgres_timescale, &self.postgres_timescale);
+        set(&mut config.output.scanner_id, &self.scanner_id);
+        set(&mut config.output.rotation.max_file_size_mb, &self.log_max_file_size_mb);
+        set(&mut config.output.rotation.compress, &self.log_compress);
+        set(&mut config.output.rotation.max_age_days, &self.log_max_age_days);
+        set(&mut config.output.rotation.max_total_size_mb, &self.log_max_total_size_mb);
+        set(&mut config.api.enabled, &self.api);
+        set(&mut config.api.listen, &self.api_listen);
+        if self.telegram_bot_token.is_some() || self.telegram_chat_ids.is_some() || self.telegram_api_url.is_some() {
+            let telegram = config.alerts.telegram.get_or_insert_with(Default::default);
+            set(&mut telegram.bot_token, &self.telegram_bot_token);
+            set(&mut telegram.chat_ids, &self.telegram_chat_ids);
+            set(&mut telegram.api_url, &self.telegram_api_url);
+        }
+    }
+}
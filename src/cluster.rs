@@ -0,0 +1,126 @@
+use anyhow::anyhow;
+use std::fmt;
+use std::str::FromStr;
+
+/// Defaults for every address the scanner needs; config keys left unset fall
+/// back to the selected cluster's value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Cluster {
+    #[default]
+    Mainnet,
+    Devnet,
+    /// A local test validator started with mainnet accounts cloned in
+    Localnet,
+}
+
+impl Cluster {
+    pub fn rpc_url(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Localnet => "http://127.0.0.1:8899",
+        }
+    }
+
+    pub fn raydium_program_id(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet | Cluster::Localnet => "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+            Cluster::Devnet => "HWy1jotHpo6UqeQxx49dpYYdQB8wj9Qk9MdxwjLvDHB8",
+        }
+    }
+
+    /// Meteora and Orca deploy under the same program id on every cluster
+    pub fn meteora_program_id(&self) -> &'static str {
+        "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo"
+    }
+
+    pub fn orca_program_id(&self) -> &'static str {
+        "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"
+    }
+
+    /// Pyth SOL/USD price account
+    pub fn sol_usd_oracle(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet | Cluster::Localnet => "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG",
+            Cluster::Devnet => "J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix",
+        }
+    }
+
+    /// Raydium AMM v4 SOL/USDC pool used as the per-cycle SOL/USD reference.
+    /// Devnet has no pool deep enough, so SOL/USD comes from the oracle there.
+    pub fn sol_usd_reference_pool(&self) -> Option<&'static str> {
+        match self {
+            Cluster::Mainnet | Cluster::Localnet => Some("58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2"),
+            Cluster::Devnet => None,
+        }
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mainnet" | "mainnet-beta" => Ok(Cluster::Mainnet),
+            "devnet" => Ok(Cluster::Devnet),
+            "localnet" | "local" => Ok(Cluster::Localnet),
+            _ => Err(anyhow!("Unknown cluster: {}. Expected mainnet, devnet or localnet", s)),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cluster::Mainnet => write!(f, "mainnet"),
+            Cluster::Devnet => write!(f, "devnet"),
+            Cluster::Localnet => write!(f, "localnet"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    const CLUSTERS: [Cluster; 3] = [Cluster::Mainnet, Cluster::Devnet, Cluster::Localnet];
+
+    #[test]
+    fn parses_cluster_names() {
+        assert_eq!("mainnet-beta".parse::<Cluster>().unwrap(), Cluster::Mainnet);
+        assert_eq!("Devnet".parse::<Cluster>().unwrap(), Cluster::Devnet);
+        assert_eq!("local".parse::<Cluster>().unwrap(), Cluster::Localnet);
+        assert!("testnet".parse::<Cluster>().is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for cluster in CLUSTERS {
+            assert_eq!(cluster.to_string().parse::<Cluster>().unwrap(), cluster);
+        }
+    }
+
+    #[test]
+    fn preset_addresses_are_pubkeys() {
+        for cluster in CLUSTERS {
+            let addresses = [
+                cluster.raydium_program_id(),
+                cluster.meteora_program_id(),
+                cluster.orca_program_id(),
+                cluster.sol_usd_oracle(),
+            ];
+            for address in addresses.into_iter().chain(cluster.sol_usd_reference_pool()) {
+                assert!(address.parse::<Pubkey>().is_ok(), "{} on {}", address, cluster);
+            }
+        }
+    }
+
+    #[test]
+    fn localnet_clones_mainnet() {
+        assert_eq!(Cluster::Localnet.rpc_url(), "http://127.0.0.1:8899");
+        assert_eq!(Cluster::Localnet.raydium_program_id(), Cluster::Mainnet.raydium_program_id());
+        assert_eq!(Cluster::Localnet.sol_usd_oracle(), Cluster::Mainnet.sol_usd_oracle());
+        assert_eq!(Cluster::Devnet.sol_usd_reference_pool(), None);
+    }
+}
@@ -0,0 +1,811 @@
+use crate::alerts::rules::Condition;
+use crate::alerts::webhook::WebhookFormat;
+use crate::alerts::THRESHOLD_RULE;
+use raytx::cluster::Cluster;
+use raytx::dex::oracle::OracleFeed;
+use raytx::dex::registry::BUILTIN_DEXES;
+use raytx::dex::ArbitrageOpportunity;
+use raytx::logging::LogFormat;
+use raytx::mints;
+use raytx::output::rotating::RotationPolicy;
+use raytx::output::{OutputFormat, Rolling};
+use raytx::price_fetcher::JUPITER_TOKEN_LIST;
+use raytx::quote::QuoteAsset;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use tracing_subscriber::EnvFilter;
+
+// Read when no --config / RAYTX_CONFIG is given and the file exists
+pub const DEFAULT_CONFIG_FILE: &str = "raytx.toml";
+
+// Every section is optional; missing keys keep the defaults below
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub rpc: RpcConfig,
+    pub dexes: DexesConfig,
+    pub tokens: TokensConfig,
+    pub quote: QuoteConfig,
+    pub oracle: OracleConfig,
+    pub thresholds: ThresholdsConfig,
+    pub output: OutputConfig,
+    pub alerts: AlertsConfig,
+    pub api: ApiConfig,
+    pub log: LogConfig,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    #[serde(deserialize_with = "from_str")]
+    pub cluster: Cluster,
+    // Unset uses the cluster's public endpoint
+    pub url: Option<String>,
+}
+
+// `[dexes.<name>]` sections by registry name. The built-in venues are
+// always listed, so a file only needs the sections it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DexesConfig(BTreeMap<String, DexConfig>);
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DexConfig {
+    pub enabled: bool,
+    // Unset uses the cluster's deployment
+    pub program_id: Option<String>,
+    // Passed to the adapter as is
+    pub options: toml::Table,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokensConfig {
+    // Jupiter-style token list fetched every cycle, empty to scan the watchlist only
+    pub source_url: String,
+    // Mints scanned every cycle in addition to the token list
+    pub watchlist: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuoteConfig {
+    #[serde(deserialize_with = "from_str")]
+    pub asset: QuoteAsset,
+    pub usd_view: bool,
+    // Unset uses the cluster's default, empty disables it
+    pub usd_reference_pool: Option<String>,
+    pub usd_fallback_oracle: Option<String>,
+    pub trade_size_usd: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OracleConfig {
+    // Unset uses the cluster's SOL/USD feed
+    #[serde(deserialize_with = "some_vec_from_str")]
+    pub feeds: Option<Vec<OracleFeed>>,
+    pub max_staleness_slots: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdsConfig {
+    pub min_price_difference: f64,
+    pub max_oracle_deviation: f64,
+    pub update_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    #[serde(deserialize_with = "vec_from_str")]
+    pub formats: Vec<OutputFormat>,
+    pub data_dir: PathBuf,
+    #[serde(deserialize_with = "from_str")]
+    pub rolling: Rolling,
+    pub postgres_url: Option<String>,
+    pub postgres_timescale: bool,
+    pub scanner_id: String,
+    pub rotation: RotationConfig,
+}
+
+// A limit of 0 disables that rule
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RotationConfig {
+    pub max_file_size_mb: u64,
+    pub compress: bool,
+    pub max_age_days: u64,
+    pub max_total_size_mb: u64,
+}
+
+// Opportunities clearing these thresholds are reported on stderr and sent to
+// the webhooks
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    pub enabled: bool,
+    pub min_net_difference: f64,
+    pub min_profit_usd: Option<f64>,
+    // Notifiers getting the threshold alerts, unset for all of them
+    pub notify: Option<Vec<String>>,
+    // Quiet period per token after an alert
+    pub cooldown_secs: u64,
+    pub rules: Vec<AlertRuleConfig>,
+    pub webhooks: Vec<WebhookConfig>,
+    pub telegram: Option<TelegramConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRuleConfig {
+    pub name: String,
+    #[serde(deserialize_with = "from_str")]
+    pub when: Condition,
+    // Notifier names; empty only logs
+    #[serde(default)]
+    pub notify: Vec<String>,
+    pub cooldown_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub name: String,
+    pub url: String,
+    #[serde(default, deserialize_with = "from_str")]
+    pub format: WebhookFormat,
+    // JSON body with `{field}` placeholders, replacing the format's default
+    pub payload: Option<serde_json::Value>,
+}
+
+// Read once at startup; commands are only answered in `chat_ids`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelegramConfig {
+    pub name: String,
+    pub bot_token: String,
+    pub chat_ids: Vec<i64>,
+    // Bot API server, e.g. a local mock
+    pub api_url: String,
+}
+
+// HTTP API serving the latest watch cycle, read once at startup
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub listen: String,
+}
+
+// Diagnostics on stderr, read once at startup
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    // EnvFilter directives, e.g. "info" or "info,raytx::dex=debug"
+    pub level: String,
+    #[serde(deserialize_with = "from_str")]
+    pub format: LogFormat,
+}
+
+fn from_str<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+fn vec_from_str<'de, D, T>(deserializer: D) -> std::result::Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| value.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn some_vec_from_str<'de, D, T>(deserializer: D) -> std::result::Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    vec_from_str(deserializer).map(Some)
+}
+
+impl Default for DexesConfig {
+    fn default() -> Self {
+        Self(BUILTIN_DEXES.iter().map(|name| (name.to_string(), DexConfig::default())).collect())
+    }
+}
+
+impl<'de> Deserialize<'de> for DexesConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let mut dexes = Self::default();
+        for (name, dex) in BTreeMap::<String, DexConfig>::deserialize(deserializer)? {
+            dexes.0.insert(name.to_lowercase(), dex);
+        }
+        Ok(dexes)
+    }
+}
+
+impl DexesConfig {
+    // Adds the section with defaults if it's missing
+    pub fn get_mut(&mut self, name: &str) -> &mut DexConfig {
+        self.0.entry(name.to_lowercase()).or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DexConfig)> {
+        self.0.iter().map(|(name, dex)| (name.as_str(), dex))
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = (&str, &DexConfig)> {
+        self.iter().filter(|(_, dex)| dex.enabled)
+    }
+}
+
+impl Default for DexConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            program_id: None,
+            options: toml::Table::new(),
+        }
+    }
+}
+
+impl Default for TokensConfig {
+    fn default() -> Self {
+        Self {
+            source_url: JUPITER_TOKEN_LIST.to_string(),
+            watchlist: Vec::new(),
+        }
+    }
+}
+
+impl Default for QuoteConfig {
+    fn default() -> Self {
+        Self {
+            asset: QuoteAsset::Sol,
+            usd_view: false,
+            usd_reference_pool: None,
+            usd_fallback_oracle: None,
+            trade_size_usd: 1000.0,
+        }
+    }
+}
+
+impl Default for OracleConfig {
+    fn default() -> Self {
+        Self {
+            feeds: None,
+            max_staleness_slots: 50,
+        }
+    }
+}
+
+impl Default for ThresholdsConfig {
+    fn default() -> Self {
+        Self {
+            min_price_difference: 1.0,
+            max_oracle_deviation: 2.0,
+            update_interval_secs: 300,
+        }
+    }
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            formats: vec![OutputFormat::Text],
+            data_dir: PathBuf::from("data"),
+            rolling: Rolling::Daily,
+            postgres_url: None,
+            postgres_timescale: false,
+            scanner_id: "default".to_string(),
+            rotation: RotationConfig::default(),
+        }
+    }
+}
+
+impl Default for RotationConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size_mb: 64,
+            compress: true,
+            max_age_days: 30,
+            max_total_size_mb: 1024,
+        }
+    }
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_net_difference: 0.0,
+            min_profit_usd: None,
+            notify: None,
+            cooldown_secs: 900,
+            rules: Vec::new(),
+            webhooks: Vec::new(),
+            telegram: None,
+        }
+    }
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        Self {
+            name: "telegram".to_string(),
+            bot_token: String::new(),
+            chat_ids: Vec::new(),
+            api_url: "https://api.telegram.org".to_string(),
+        }
+    }
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: "127.0.0.1:8080".to_string(),
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::Text,
+        }
+    }
+}
+
+impl RotationConfig {
+    pub fn policy(&self) -> RotationPolicy {
+        RotationPolicy {
+            max_file_size: Some(self.max_file_size_mb * 1024 * 1024).filter(|&size| size > 0),
+            compress: self.compress,
+            max_age: Some(Duration::from_secs(self.max_age_days * 24 * 60 * 60)).filter(|age| !age.is_zero()),
+            max_total_size: Some(self.max_total_size_mb * 1024 * 1024).filter(|&size| size > 0),
+        }
+    }
+}
+
+impl AlertsConfig {
+    pub fn matches(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        let profitable = match self.min_profit_usd {
+            Some(min_profit_usd) => opportunity.profit_usd.map(|profit| profit >= min_profit_usd).unwrap_or(false),
+            None => true,
+        };
+        self.enabled && opportunity.net_difference_percent >= self.min_net_difference && profitable
+    }
+}
+
+// Empty disables the address, unset falls back to the cluster default
+fn address_or<'a>(address: &'a Option<String>, cluster_default: Option<&'a str>) -> Option<&'a str> {
+    match address.as_deref() {
+        Some("") => None,
+        Some(address) => Some(address),
+        None => cluster_default,
+    }
+}
+
+impl Config {
+    pub fn rpc_url(&self) -> &str {
+        self.rpc.url.as_deref().unwrap_or(self.rpc.cluster.rpc_url())
+    }
+
+    pub fn usd_reference_pool(&self) -> Option<&str> {
+        address_or(&self.quote.usd_reference_pool, self.rpc.cluster.sol_usd_reference_pool())
+    }
+
+    pub fn usd_fallback_oracle(&self) -> Option<&str> {
+        address_or(&self.quote.usd_fallback_oracle, Some(self.rpc.cluster.sol_usd_oracle()))
+    }
+
+    pub fn oracle_feeds(&self) -> Result<Vec<OracleFeed>> {
+        match &self.oracle.feeds {
+            Some(feeds) => Ok(feeds.clone()),
+            None => format!("{}:pyth:{}", mints::WSOL.address, self.rpc.cluster.sol_usd_oracle())
+                .parse()
+                .map(|feed| vec![feed]),
+        }
+    }
+
+    // An explicit path must exist; otherwise raytx.toml is used when present
+    pub fn resolve_path(path: Option<&Path>) -> Option<PathBuf> {
+        match path {
+            Some(path) => Some(path.to_path_buf()),
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => Some(PathBuf::from(DEFAULT_CONFIG_FILE)),
+            None => None,
+        }
+    }
+
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    // One line per section that differs from `new`
+    pub fn changes(&self, new: &Config) -> Vec<String> {
+        fn section<T: PartialEq + Debug>(changes: &mut Vec<String>, name: &str, old: &T, new: &T) {
+            if old != new {
+                changes.push(format!("{}: {:?} -> {:?}", name, old, new));
+            }
+        }
+
+        let mut changes = Vec::new();
+        section(&mut changes, "rpc", &self.rpc, &new.rpc);
+        section(&mut changes, "dexes", &self.dexes, &new.dexes);
+        section(&mut changes, "tokens", &self.tokens, &new.tokens);
+        section(&mut changes, "quote", &self.quote, &new.quote);
+        section(&mut changes, "oracle", &self.oracle, &new.oracle);
+        section(&mut changes, "thresholds", &self.thresholds, &new.thresholds);
+        section(&mut changes, "output", &self.output, &new.output);
+        section(&mut changes, "alerts", &self.alerts, &new.alerts);
+        section(&mut changes, "api", &self.api, &new.api);
+        section(&mut changes, "log", &self.log, &new.log);
+        changes
+    }
+
+    // Checks what the types can't express, reporting every problem at once
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        let url = self.rpc_url();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            errors.push(format!("rpc.url must be an http(s) URL, got {}", url));
+        }
+
+        for (name, dex) in self.dexes.iter() {
+            if let Some(Err(e)) = dex.program_id.as_deref().map(Pubkey::from_str) {
+                errors.push(format!("dexes.{}.program_id is not a valid pubkey: {}", name, e));
+            }
+        }
+        if self.dexes.enabled().next().is_none() {
+            errors.push("dexes: at least one DEX must be enabled".to_string());
+        }
+
+        if self.tokens.source_url.is_empty() && self.tokens.watchlist.is_empty() {
+            errors.push("tokens: set source_url or a non-empty watchlist".to_string());
+        }
+        for mint in &self.tokens.watchlist {
+            if let Err(e) = Pubkey::from_str(mint) {
+                errors.push(format!("tokens.watchlist entry {} is not a valid mint: {}", mint, e));
+            }
+        }
+
+        for (name, address) in [
+            ("usd_reference_pool", self.usd_reference_pool()),
+            ("usd_fallback_oracle", self.usd_fallback_oracle()),
+        ] {
+            if let Some(Err(e)) = address.map(Pubkey::from_str) {
+                errors.push(format!("quote.{} is not a valid pubkey: {}", name, e));
+            }
+        }
+        if self.quote.trade_size_usd <= 0.0 {
+            errors.push(format!("quote.trade_size_usd must be positive, got {}", self.quote.trade_size_usd));
+        }
+
+        if self.thresholds.min_price_difference < 0.0 {
+            errors.push(format!(
+                "thresholds.min_price_difference must not be negative, got {}",
+                self.thresholds.min_price_difference
+            ));
+        }
+        if self.thresholds.max_oracle_deviation < 0.0 {
+            errors.push(format!(
+                "thresholds.max_oracle_deviation must not be negative, got {}",
+                self.thresholds.max_oracle_deviation
+            ));
+        }
+        if self.thresholds.update_interval_secs == 0 {
+            errors.push("thresholds.update_interval_secs must be at least 1".to_string());
+        }
+
+        if self.output.formats.is_empty() {
+            errors.push("output.formats must list at least one sink".to_string());
+        }
+        #[cfg(feature = "postgres")]
+        if self.output.formats.contains(&OutputFormat::Postgres)
+            && self.output.postgres_url.as_deref().unwrap_or_default().is_empty()
+        {
+            errors.push("output.postgres_url is required by the postgres sink (or set POSTGRES_URL)".to_string());
+        }
+
+        if self.alerts.enabled && self.alerts.min_net_difference < 0.0 {
+            errors.push(format!(
+                "alerts.min_net_difference must not be negative, got {}",
+                self.alerts.min_net_difference
+            ));
+        }
+        for (i, webhook) in self.alerts.webhooks.iter().enumerate() {
+            if webhook.name.is_empty() {
+                errors.push(format!("alerts.webhooks[{}].name must not be empty", i));
+            } else if self.alerts.webhooks[..i].iter().any(|other| other.name == webhook.name) {
+                errors.push(format!("alerts.webhooks[{}].name {} is used twice", i, webhook.name));
+            }
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                errors.push(format!("alerts.webhooks[{}].url must be an http(s) URL", i));
+            }
+        }
+        if let Some(telegram) = &self.alerts.telegram {
+            if telegram.bot_token.is_empty() {
+                errors.push("alerts.telegram.bot_token is required (or set TELEGRAM_BOT_TOKEN)".to_string());
+            }
+            if telegram.chat_ids.is_empty() {
+                errors.push("alerts.telegram.chat_ids must list at least one chat".to_string());
+            }
+            if !telegram.api_url.starts_with("http://") && !telegram.api_url.starts_with("https://") {
+                errors.push(format!("alerts.telegram.api_url must be an http(s) URL, got {}", telegram.api_url));
+            }
+            if self.alerts.webhooks.iter().any(|webhook| webhook.name == telegram.name) {
+                errors.push(format!("alerts.telegram.name {} is also used by a webhook", telegram.name));
+            }
+        }
+
+        let notifiers: Vec<&str> = self
+            .alerts
+            .webhooks
+            .iter()
+            .map(|webhook| webhook.name.as_str())
+            .chain(self.alerts.telegram.iter().map(|telegram| telegram.name.as_str()))
+            .collect();
+        for name in self.alerts.notify.iter().flatten() {
+            if !notifiers.contains(&name.as_str()) {
+                errors.push(format!("alerts.notify names unknown notifier {}", name));
+            }
+        }
+        for (i, rule) in self.alerts.rules.iter().enumerate() {
+            if rule.name.is_empty() || rule.name == THRESHOLD_RULE {
+                errors.push(format!("alerts.rules[{}].name must be set and not {}", i, THRESHOLD_RULE));
+            } else if self.alerts.rules[..i].iter().any(|other| other.name == rule.name) {
+                errors.push(format!("alerts.rules[{}].name {} is used twice", i, rule.name));
+            }
+            for name in &rule.notify {
+                if !notifiers.contains(&name.as_str()) {
+                    errors.push(format!("alerts.rules[{}].notify names unknown notifier {}", i, name));
+                }
+            }
+            let usd_fields = rule.when.usd_fields();
+            if !self.quote.usd_view && !usd_fields.is_empty() {
+                errors.push(format!("alerts.rules[{}] reads {}, which need quote.usd_view", i, usd_fields.join(", ")));
+            }
+        }
+        if !self.quote.usd_view && self.alerts.min_profit_usd.is_some() {
+            errors.push("alerts.min_profit_usd needs quote.usd_view".to_string());
+        }
+
+        if self.api.enabled {
+            if let Err(e) = SocketAddr::from_str(&self.api.listen) {
+                errors.push(format!("api.listen must be <ip>:<port>, got {}: {}", self.api.listen, e));
+            }
+        }
+
+        if let Err(e) = EnvFilter::try_new(&self.log.level) {
+            errors.push(format!("log.level is not a valid filter, got {}: {}", self.log.level, e));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid configuration:\n  {}", errors.join("\n  ")))
+        }
+    }
+}
+
+// Polls the config file's modification time; the watch loop checks it between
+// cycles so a reload never lands mid-scan
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: Self::modified(path),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    pub fn changed(&mut self) -> bool {
+        let modified = Self::modified(&self.path);
+        if modified.is_some() && modified != self.modified {
+            self.modified = modified;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(toml: &str) -> Result<Config> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        Config::default().validate().unwrap();
+    }
+
+    #[test]
+    fn example_file_is_valid() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("raytx.example.toml");
+        let config = Config::load(Some(&path)).unwrap();
+
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn missing_keys_keep_defaults() {
+        let config = parse("[thresholds]\nmin_price_difference = 2.5\n").unwrap();
+
+        assert_eq!(config.thresholds.min_price_difference, 2.5);
+        assert_eq!(config.thresholds.update_interval_secs, 300);
+        assert_eq!(config.dexes.enabled().count(), BUILTIN_DEXES.len());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let error = parse("[thresholds]\nmin_price_diference = 2.5\n").unwrap_err();
+        assert!(format!("{:#}", error).contains("min_price_diference"), "{:#}", error);
+    }
+
+    #[test]
+    fn dex_sections_merge_with_builtins() {
+        let config = parse("[dexes.Meteora]\nenabled = false\n").unwrap();
+        let enabled: Vec<&str> = config.dexes.enabled().map(|(name, _)| name).collect();
+
+        assert_eq!(enabled, ["orca", "raydium"]);
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let config = parse(
+            r#"
+            [rpc]
+            url = "ws://localhost"
+
+            [tokens]
+            source_url = ""
+            watchlist = ["not-a-mint"]
+
+            [quote]
+            trade_size_usd = 0.0
+
+            [thresholds]
+            update_interval_secs = 0
+            "#,
+        )
+        .unwrap();
+
+        let error = config.validate().unwrap_err().to_string();
+        for key in ["rpc.url", "tokens.watchlist", "quote.trade_size_usd", "thresholds.update_interval_secs"] {
+            assert!(error.contains(key), "{} missing from {}", key, error);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_all_dexes_disabled() {
+        let mut config = Config::default();
+        for name in BUILTIN_DEXES {
+            config.dexes.get_mut(name).enabled = false;
+        }
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("at least one DEX"), "{}", error);
+    }
+
+    #[test]
+    fn validate_checks_notifier_names() {
+        let config = parse(
+            r#"
+            [alerts]
+            notify = ["trading"]
+
+            [[alerts.webhooks]]
+            name = "ops"
+            url = "https://example.com/hook"
+
+            [[alerts.webhooks]]
+            name = "ops"
+            url = "ftp://example.com"
+            "#,
+        )
+        .unwrap();
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("unknown notifier trading"), "{}", error);
+        assert!(error.contains("alerts.webhooks[1].name ops is used twice"), "{}", error);
+        assert!(error.contains("alerts.webhooks[1].url"), "{}", error);
+    }
+
+    #[test]
+    fn validate_requires_usd_view_for_usd_fields() {
+        let rules = r#"
+            [alerts]
+            min_profit_usd = 25.0
+
+            [[alerts.rules]]
+            name = "deep"
+            when = "liquidity_sol > 50 or profit_usd > 10"
+
+            [[alerts.rules]]
+            name = "pump"
+            when = "price change 5m > 10%"
+            "#;
+
+        let error = parse(rules).unwrap().validate().unwrap_err().to_string();
+        assert!(error.contains("alerts.rules[0] reads liquidity_sol, profit_usd"), "{}", error);
+        assert!(error.contains("alerts.min_profit_usd"), "{}", error);
+        assert!(!error.contains("alerts.rules[1]"), "{}", error);
+
+        let config = parse(&format!("[quote]\nusd_view = true\n{}", rules)).unwrap();
+        assert!(config.validate().is_ok(), "{:?}", config.validate());
+    }
+
+    #[test]
+    fn empty_address_disables_cluster_default() {
+        let config = parse("[quote]\nusd_reference_pool = \"\"\n").unwrap();
+
+        assert_eq!(config.usd_reference_pool(), None);
+        assert_eq!(config.usd_fallback_oracle(), Some(Cluster::Mainnet.sol_usd_oracle()));
+    }
+
+    #[test]
+    fn cluster_picks_default_addresses() {
+        let config = parse("[rpc]\ncluster = \"devnet\"\n").unwrap();
+
+        assert_eq!(config.rpc_url(), Cluster::Devnet.rpc_url());
+        assert_eq!(config.usd_reference_pool(), None);
+        assert_eq!(config.usd_fallback_oracle(), Some(Cluster::Devnet.sol_usd_oracle()));
+        assert_eq!(config.oracle_feeds().unwrap()[0].account.to_string(), Cluster::Devnet.sol_usd_oracle());
+    }
+
+    #[test]
+    fn changes_name_the_sections() {
+        let mut config = Config::default();
+        config.thresholds.min_price_difference = 3.0;
+
+        let changes = Config::default().changes(&config);
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].starts_with("thresholds:"), "{}", changes[0]);
+    }
+}
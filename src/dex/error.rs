@@ -0,0 +1,160 @@
+use serde::Serialize;
+use solana_client::client_error::reqwest::StatusCode;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::RpcError;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// Why a venue couldn't price a token, so "no pool exists" can be told apart
+/// from "the RPC is failing"
+#[derive(Debug, Error)]
+pub enum DexError {
+    #[error("no pool found for {mint}")]
+    PoolNotFound { mint: String },
+    #[error("RPC error: {0}")]
+    RpcError(String),
+    #[error("RPC rate limited: {0}")]
+    RateLimited(String),
+    #[error("unexpected account size {actual_len}, expected {expected_len}")]
+    DecodeError { expected_len: usize, actual_len: usize },
+    #[error("price is {age_slots} slots old, limit is {max_slots}")]
+    Stale { age_slots: u64, max_slots: u64 },
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl DexError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DexError::PoolNotFound { .. } => "pool_not_found",
+            DexError::RpcError(_) => "rpc_error",
+            DexError::RateLimited(_) => "rate_limited",
+            DexError::DecodeError { .. } => "decode_error",
+            DexError::Stale { .. } => "stale",
+            DexError::Other(_) => "other",
+        }
+    }
+
+    pub fn pool_not_found(mint: &str) -> Self {
+        DexError::PoolNotFound { mint: mint.to_string() }
+    }
+}
+
+// JSON-RPC error codes providers use for rate limiting when they answer
+// with HTTP 200 rather than 429
+const RATE_LIMIT_CODES: [i64; 2] = [429, -32429];
+
+impl From<ClientError> for DexError {
+    fn from(e: ClientError) -> Self {
+        let rate_limited = match e.kind() {
+            ClientErrorKind::Reqwest(e) => e.status() == Some(StatusCode::TOO_MANY_REQUESTS),
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => RATE_LIMIT_CODES.contains(code),
+            _ => false,
+        };
+
+        if rate_limited {
+            DexError::RateLimited(e.to_string())
+        } else {
+            DexError::RpcError(e.to_string())
+        }
+    }
+}
+
+impl From<Box<ClientError>> for DexError {
+    fn from(e: Box<ClientError>) -> Self {
+        (*e).into()
+    }
+}
+
+// Adapter helpers return anyhow errors wrapping the underlying client error
+impl From<anyhow::Error> for DexError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<DexError>() {
+            Ok(e) => return e,
+            Err(e) => e,
+        };
+        let e = match e.downcast::<Box<ClientError>>() {
+            Ok(e) => return e.into(),
+            Err(e) => e,
+        };
+        match e.downcast::<ClientError>() {
+            Ok(e) => e.into(),
+            Err(e) => DexError::Other(e),
+        }
+    }
+}
+
+pub fn parse_mint(mint: &str) -> Result<Pubkey, DexError> {
+    Pubkey::from_str(mint).map_err(|e| DexError::Other(anyhow::anyhow!("Invalid mint {}: {}", mint, e)))
+}
+
+/// Lookup failures of one pass, counted per venue and kind
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LookupErrors(BTreeMap<String, BTreeMap<&'static str, usize>>);
+
+impl LookupErrors {
+    pub fn record(&mut self, dex: &str, error: &DexError) {
+        *self.0.entry(dex.to_string()).or_default().entry(error.kind()).or_default() += 1;
+    }
+
+    /// One line per venue: missing pools are expected, anything else is a warning
+    pub fn log(&self) {
+        for (dex, kinds) in &self.0 {
+            let summary = kinds
+                .iter()
+                .map(|(kind, count)| format!("{}={}", kind, count))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            if kinds.keys().all(|kind| *kind == "pool_not_found") {
+                debug!(dex = %dex, errors = %summary, "Lookup errors this cycle");
+            } else {
+                warn!(dex = %dex, errors = %summary, "Lookup errors this cycle");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcResponseErrorData;
+
+    fn response_error(code: i64, message: &str) -> ClientError {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code,
+            message: message.to_string(),
+            data: RpcResponseErrorData::Empty,
+        })
+        .into()
+    }
+
+    #[test]
+    fn rate_limit_codes_are_rate_limited() {
+        for code in RATE_LIMIT_CODES {
+            let error = DexError::from(response_error(code, "Too many requests"));
+            assert_eq!(error.kind(), "rate_limited", "{}", code);
+        }
+    }
+
+    #[test]
+    fn other_errors_are_not_classified_by_message() {
+        let error = DexError::from(response_error(-32602, "429 Too Many Requests"));
+        assert_eq!(error.kind(), "rpc_error");
+
+        let error = DexError::from(ClientError::from(ClientErrorKind::Custom("429 Too Many Requests".to_string())));
+        assert_eq!(error.kind(), "rpc_error");
+    }
+
+    #[test]
+    fn anyhow_wrapped_client_errors_keep_their_kind() {
+        let error = DexError::from(anyhow::Error::from(response_error(429, "slow down")));
+        assert_eq!(error.kind(), "rate_limited");
+
+        let error = DexError::from(anyhow::Error::from(Box::new(response_error(429, "slow down"))));
+        assert_eq!(error.kind(), "rate_limited");
+    }
+}
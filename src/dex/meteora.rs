@@ -1,19 +1,18 @@
-use super::DexProtocol;
-use anyhow::{anyhow, Result};
+use super::error::parse_mint;
+use super::{DexError, DexProtocol, PoolInfo, PoolPrice};
+use crate::mints;
+use anyhow::Result;
 use async_trait::async_trait;
-use solana_client::rpc_client::RpcClient;
+use crate::rpc::Rpc;
+use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
 use std::str::FromStr;
 use solana_client::rpc_filter::{RpcFilterType, Memcmp};
-use solana_client::rpc_config::{RpcProgramAccountsConfig, RpcAccountInfoConfig};
-use solana_account_decoder::UiAccountEncoding;
-use solana_sdk::program_pack::Pack;
-use spl_token::state::Mint;
 
-const METEORA_PROGRAM_ID: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
 const POOL_STATE_SIZE: usize = 396; // Size of Meteora pool state account
 
+/// Meteora dynamic AMM pools
 #[derive(Clone)]
 pub struct MeteoraDex {
     program_id: Pubkey,
@@ -23,57 +22,49 @@ pub struct MeteoraDex {
 struct PoolState {
     token_mint_a: Pubkey,
     token_mint_b: Pubkey,
-    token_vault_a: Pubkey,
-    token_vault_b: Pubkey,
     reserve_a: u64,
     reserve_b: u64,
 }
 
 impl MeteoraDex {
-    pub fn new(program_id: Option<&str>) -> Result<Self> {
+    pub fn new(program_id: &str) -> Result<Self> {
         Ok(Self {
-            program_id: Pubkey::from_str(
-                program_id.unwrap_or(METEORA_PROGRAM_ID)
-            )?,
+            program_id: Pubkey::from_str(program_id)?,
         })
     }
 
     async fn get_pool_price(
         &self,
-        rpc_client: Arc<RpcClient>,
+        rpc_client: Arc<dyn Rpc>,
         token_mint: &str,
-    ) -> Result<Option<f64>> {
-        // First try USDC pool
-        if let Some(price) = Self::get_price_from_pool(
-            self,
-            rpc_client.clone(),
-            token_mint,
-            "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", // USDC mint
-        ).await? {
-            return Ok(Some(price));
-        }
-
-        // Then try SOL pool
-        if let Some(price) = Self::get_price_from_pool(
-            self,
-            rpc_client,
-            token_mint,
-            "So111111111111111111111111111111111111111123", // SOL mint
-        ).await? {
-            return Ok(Some(price));
+    ) -> Result<PoolPrice, DexError> {
+        // First try USDC pool, then SOL pool
+        for quote_mint in [mints::USDC, mints::WSOL] {
+            if let Some((price, liquidity)) = Self::get_price_from_pool(
+                self,
+                rpc_client.clone(),
+                token_mint,
+                quote_mint.address,
+            ).await? {
+                return Ok(PoolPrice {
+                    price,
+                    quote_mint: quote_mint.pubkey(),
+                    liquidity: Some(liquidity),
+                });
+            }
         }
 
-        Ok(None)
+        Err(DexError::pool_not_found(token_mint))
     }
 
     async fn get_price_from_pool(
         &self,
-        rpc_client: Arc<RpcClient>,
+        rpc_client: Arc<dyn Rpc>,
         token_a_mint: &str,
         token_b_mint: &str,
-    ) -> Result<Option<f64>> {
-        let token_a = Pubkey::from_str(token_a_mint)?;
-        let token_b = Pubkey::from_str(token_b_mint)?;
+    ) -> Result<Option<(f64, f64)>, DexError> {
+        let token_a = parse_mint(token_a_mint)?;
+        let token_b = parse_mint(token_b_mint)?;
 
         let filters = vec![
             RpcFilterType::DataSize(POOL_STATE_SIZE as u64),
@@ -87,16 +78,7 @@ impl MeteoraDex {
             )),
         ];
 
-        let config = RpcProgramAccountsConfig {
-            filters: Some(filters),
-            account_config: RpcAccountInfoConfig {
-                encoding: Some(UiAccountEncoding::Base64),
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-
-        let accounts = rpc_client.get_program_accounts_with_config(&self.program_id, config)?;
+        let accounts = rpc_client.get_program_accounts(&self.program_id, filters)?;
 
         if accounts.is_empty() {
             return Ok(None);
@@ -104,39 +86,64 @@ impl MeteoraDex {
 
         let mut highest_liquidity = 0u64;
         let mut best_pool = None;
+        let mut decode_error = None;
 
         for (_, account) in accounts {
-            if let Ok(pool) = Self::deserialize_pool_state(&account.data) {
-                let liquidity = pool.reserve_a.saturating_add(pool.reserve_b);
-                if liquidity > highest_liquidity {
-                    highest_liquidity = liquidity;
-                    best_pool = Some(pool);
+            match Self::deserialize_pool_state(&account.data) {
+                // Emptied pools have no price
+                Ok(pool) if pool.reserve_a == 0 || pool.reserve_b == 0 => {}
+                Ok(pool) => {
+                    let liquidity = pool.reserve_a.saturating_add(pool.reserve_b);
+                    if liquidity > highest_liquidity {
+                        highest_liquidity = liquidity;
+                        best_pool = Some(pool);
+                    }
                 }
+                Err(e) => decode_error = Some(e),
             }
         }
 
-        let pool = best_pool.ok_or_else(|| anyhow!("Failed to deserialize any valid pools"))?;
+        // Pools were found but none could be read
+        let pool = match (best_pool, decode_error) {
+            (Some(pool), _) => pool,
+            (None, Some(e)) => return Err(e),
+            (None, None) => return Ok(None),
+        };
+
+        Ok(Some(Self::price_from_pool_state(rpc_client.as_ref(), &pool).await?))
+    }
+
+    // Price of token A in token B and the pool's total value in token B
+    async fn price_from_pool_state(rpc_client: &dyn Rpc, pool: &PoolState) -> Result<(f64, f64)> {
+        if pool.reserve_a == 0 || pool.reserve_b == 0 {
+            anyhow::bail!("Pool has an empty reserve");
+        }
 
         // Calculate price from reserves
         let price = pool.reserve_b as f64 / pool.reserve_a as f64;
 
-        // Adjust for decimals
-        let token_a_decimals = Self::get_token_decimals(&rpc_client, &token_a).await?;
-        let token_b_decimals = Self::get_token_decimals(&rpc_client, &token_b).await?;
-        let decimal_adjustment = 10_f64.powi(token_b_decimals as i32 - token_a_decimals as i32);
+        // Reserves are in base units, so scale by the decimals A has over B
+        let token_a_decimals = Self::get_token_decimals(rpc_client, &pool.token_mint_a).await?;
+        let token_b_decimals = Self::get_token_decimals(rpc_client, &pool.token_mint_b).await?;
+        let decimal_adjustment = 10_f64.powi(token_a_decimals as i32 - token_b_decimals as i32);
 
-        Ok(Some(price * decimal_adjustment))
+        // Total pool value in token B terms, both sides at the pool price
+        let reserve_b = pool.reserve_b as f64 / 10_f64.powi(token_b_decimals as i32);
+
+        Ok((price * decimal_adjustment, reserve_b * 2.0))
     }
 
-    async fn get_token_decimals(rpc_client: &RpcClient, mint: &Pubkey) -> Result<u8> {
-        let account = rpc_client.get_account(mint)?;
-        let mint_data = Mint::unpack_from_slice(&account.data)?;
-        Ok(mint_data.decimals)
+    async fn get_token_decimals(rpc_client: &dyn Rpc, mint: &Pubkey) -> Result<u8> {
+        // Decimals don't depend on the epoch, only the transfer fee does
+        Ok(mints::fetch_mint_info(rpc_client, mint, 0)?.decimals)
     }
 
-    fn deserialize_pool_state(data: &[u8]) -> Result<PoolState> {
+    fn deserialize_pool_state(data: &[u8]) -> Result<PoolState, DexError> {
         if data.len() < POOL_STATE_SIZE {
-            return Err(anyhow!("Data length too short for Pool State account"));
+            return Err(DexError::DecodeError {
+                expected_len: POOL_STATE_SIZE,
+                actual_len: data.len(),
+            });
         }
 
         // Skip 8 bytes discriminator
@@ -145,10 +152,9 @@ impl MeteoraDex {
         Ok(PoolState {
             token_mint_a: Pubkey::try_from(&data[0..32]).unwrap(),
             token_mint_b: Pubkey::try_from(&data[32..64]).unwrap(),
-            token_vault_a: Pubkey::try_from(&data[64..96]).unwrap(),
-            token_vault_b: Pubkey::try_from(&data[96..128]).unwrap(),
-            reserve_a: u64::from_le_bytes(data[128..136].try_into()?),
-            reserve_b: u64::from_le_bytes(data[136..144].try_into()?),
+            // The token vaults sit at 64..128; prices only need the reserves
+            reserve_a: u64::from_le_bytes(data[128..136].try_into().unwrap()),
+            reserve_b: u64::from_le_bytes(data[136..144].try_into().unwrap()),
         })
     }
 }
@@ -159,11 +165,25 @@ impl DexProtocol for MeteoraDex {
         "Meteora"
     }
 
-    fn clone_box(&self) -> Box<dyn DexProtocol + Send + Sync> {
-        Box::new(self.clone())
+    async fn get_token_price(&self, rpc_client: Arc<dyn Rpc>, token_mint: &str) -> Result<PoolPrice, DexError> {
+        Self::get_pool_price(self, rpc_client, token_mint).await
     }
 
-    async fn get_token_price(&self, rpc_client: Arc<RpcClient>, token_mint: &str) -> Result<Option<f64>> {
-        Self::get_pool_price(self, rpc_client, token_mint).await
+    async fn decode_pool(&self, rpc_client: Arc<dyn Rpc>, address: &Pubkey, account: &Account) -> Result<Option<PoolInfo>> {
+        if account.owner != self.program_id {
+            return Ok(None);
+        }
+
+        let pool = Self::deserialize_pool_state(&account.data)?;
+        let (price, liquidity) = Self::price_from_pool_state(rpc_client.as_ref(), &pool).await?;
+
+        Ok(Some(PoolInfo {
+            address: address.to_string(),
+            dex_name: self.name().to_string(),
+            base_mint: pool.token_mint_a.to_string(),
+            quote_mint: pool.token_mint_b.to_string(),
+            price,
+            liquidity: Some(liquidity),
+        }))
     }
 }
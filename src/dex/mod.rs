@@ -1,84 +1,191 @@
 use anyhow::Result;
 use chrono::{DateTime, Local};
-use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::metrics;
+use crate::rpc::Rpc;
+use tracing::{debug_span, Instrument};
+use crate::mints::TransferFee;
 
+pub mod error;
+#[cfg(feature = "raydium")]
 pub mod raydium;
+#[cfg(feature = "meteora")]
 pub mod meteora;
+#[cfg(feature = "orca")]
 pub mod orca;
+pub mod oracle;
+pub mod registry;
 
-use raydium::RaydiumDex;
-use meteora::MeteoraDex;
-use orca::OrcaDex;
+pub use error::{DexError, LookupErrors};
+pub use registry::{DexRegistry, DexSettings};
 
+/// A venue that can price tokens from on-chain accounts. Adapters are shared
+/// as `Arc<dyn DexProtocol>`; see [`DexRegistry`] for adding one.
 #[async_trait]
 pub trait DexProtocol: Send + Sync {
     fn name(&self) -> &str;
-    fn clone_box(&self) -> Box<dyn DexProtocol + Send + Sync>;
-    async fn get_token_price(&self, rpc_client: Arc<RpcClient>, token_mint: &str) -> Result<Option<f64>>;
+    /// Price of `token_mint` in its deepest pool on this venue
+    async fn get_token_price(&self, rpc_client: Arc<dyn Rpc>, token_mint: &str) -> std::result::Result<PoolPrice, DexError>;
+
+    /// Decodes `account` if it's a pool owned by this venue
+    async fn decode_pool(&self, _rpc_client: Arc<dyn Rpc>, _address: &Pubkey, _account: &Account) -> Result<Option<PoolInfo>> {
+        Ok(None)
+    }
+}
+
+/// Raw price as quoted by a pool, in units of `quote_mint`. `liquidity` is the
+/// pool's total value in the same units, when the adapter can read reserves.
+#[derive(Debug, Clone, Copy)]
+pub struct PoolPrice {
+    pub price: f64,
+    pub quote_mint: Pubkey,
+    pub liquidity: Option<f64>,
+}
+
+/// A single pool account as stored on chain, priced in `quote_mint` per `base_mint`
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolInfo {
+    pub address: String,
+    pub dex_name: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub price: f64,
+    pub liquidity: Option<f64>,
 }
 
-#[derive(Debug, Clone)]
+/// A venue's price for a token, normalized to the fetcher's quote asset
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenPrice {
     pub token_address: String,
     pub dex_name: String,
     pub price: f64,
+    pub quote_mint: String,
+    pub price_usd: Option<f64>,
+    pub liquidity_usd: Option<f64>,
     pub timestamp: DateTime<Local>,
 }
 
-#[derive(Clone)]
-pub enum DexType {
-    Raydium(RaydiumDex),
-    Meteora(MeteoraDex),
-    Orca(OrcaDex),
+/// Prices `token_mint` on one venue, traced and counted in [`crate::metrics`]
+pub async fn lookup(dex: &dyn DexProtocol, rpc_client: Arc<dyn Rpc>, token_mint: &str) -> std::result::Result<PoolPrice, DexError> {
+    let result = dex
+        .get_token_price(rpc_client, token_mint)
+        .instrument(debug_span!("dex", dex = dex.name()))
+        .await;
+    metrics::record_dex_lookup(dex.name(), &result);
+    result
 }
 
-impl DexType {
-    pub fn name(&self) -> &str {
-        match self {
-            DexType::Raydium(_) => "Raydium",
-            DexType::Meteora(_) => "Meteora",
-            DexType::Orca(_) => "Orca",
-        }
-    }
-
-    pub async fn get_token_price(&self, rpc_client: Arc<RpcClient>, token_mint: &str) -> Result<Option<f64>> {
-        match self {
-            DexType::Raydium(dex) => dex.get_token_price(rpc_client, token_mint).await,
-            DexType::Meteora(dex) => dex.get_token_price(rpc_client, token_mint).await,
-            DexType::Orca(dex) => dex.get_token_price(rpc_client, token_mint).await,
-        }
-    }
-}
-
-#[derive(Debug)]
+/// Spread of one token between two venues, bought where it's cheaper and
+/// sold where it's dearer
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArbitrageOpportunity {
     pub token_address: String,
     pub token_name: Option<String>,
-    pub raydium_price: f64,
-    pub meteora_price: f64,
+    pub buy_venue: String,
+    pub buy_price: f64,
+    pub sell_venue: String,
+    pub sell_price: f64,
+    pub quote_mint: String,
     pub price_difference_percent: f64,
+    pub transfer_fee_bps: u16,
+    pub net_difference_percent: f64,
+    pub price_usd: Option<f64>,
+    pub liquidity_usd: Option<f64>,
+    pub profit_usd: Option<f64>,
     pub timestamp: DateTime<Local>,
 }
 
 impl ArbitrageOpportunity {
+    /// `a` and `b` are (venue, price) in either order; the cheaper one
+    /// becomes the buy side
     pub fn new(
-        token_address: String, 
+        token_address: String,
         token_name: Option<String>,
-        raydium_price: f64, 
-        meteora_price: f64
+        a: (&str, f64),
+        b: (&str, f64),
+        quote_mint: String,
     ) -> Self {
-        let price_difference_percent = ((raydium_price - meteora_price).abs() / 
-            meteora_price.min(raydium_price)) * 100.0;
-            
+        let ((buy_venue, buy_price), (sell_venue, sell_price)) = if a.1 <= b.1 { (a, b) } else { (b, a) };
+        let price_difference_percent = (sell_price - buy_price) / buy_price * 100.0;
+
         Self {
             token_address,
             token_name,
-            raydium_price,
-            meteora_price, 
+            buy_venue: buy_venue.to_string(),
+            buy_price,
+            sell_venue: sell_venue.to_string(),
+            sell_price,
+            quote_mint,
             price_difference_percent,
+            transfer_fee_bps: 0,
+            net_difference_percent: price_difference_percent,
+            price_usd: None,
+            liquidity_usd: None,
+            profit_usd: None,
             timestamp: Local::now(),
         }
     }
-} 
+
+    /// Token-2022 transfer fees are withheld when the token leaves the cheaper
+    /// pool and again when it enters the dearer one
+    pub fn with_transfer_fee(mut self, transfer_fee: Option<TransferFee>) -> Self {
+        if let Some(fee) = transfer_fee {
+            let retained = (1.0 - fee.rate()).powi(2);
+
+            self.transfer_fee_bps = fee.basis_points;
+            self.net_difference_percent = (self.sell_price * retained - self.buy_price) / self.buy_price * 100.0;
+        }
+
+        self
+    }
+
+    /// (venue, price) to buy on and to sell on
+    pub fn venues(&self) -> ((&str, f64), (&str, f64)) {
+        ((&self.buy_venue, self.buy_price), (&self.sell_venue, self.sell_price))
+    }
+
+    /// Fills in the USD view from both venues' prices. Profit assumes buying
+    /// `trade_size_usd` on the cheaper venue and selling on the dearer one,
+    /// capped at 1% of the shallower pool, net of transfer fees but ignoring
+    /// slippage and swap fees.
+    pub fn with_usd(mut self, a: &TokenPrice, b: &TokenPrice, trade_size_usd: f64) -> Self {
+        self.price_usd = match (a.price_usd, b.price_usd) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        self.liquidity_usd = match (a.liquidity_usd, b.liquidity_usd) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            _ => None,
+        };
+
+        let notional = match self.liquidity_usd {
+            Some(liquidity) => trade_size_usd.min(liquidity * 0.01),
+            None => trade_size_usd,
+        };
+        self.profit_usd = self
+            .price_usd
+            .map(|_| notional * self.net_difference_percent / 100.0);
+
+        self
+    }
+}
+
+/// AMM price compared against an oracle reference for the same token, both
+/// expressed in `quote_mint`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OracleDeviation {
+    pub token_address: String,
+    pub dex_name: String,
+    pub dex_price: f64,
+    pub oracle_price: f64,
+    pub oracle_confidence: f64,
+    pub oracle_stale: bool,
+    pub quote_mint: String,
+    pub deviation_percent: f64,
+    pub timestamp: DateTime<Local>,
+}
@@ -0,0 +1,135 @@
+use super::{DexError, DexProtocol, PoolPrice};
+use crate::mints;
+use crate::oracle::{self, OracleKind, OraclePrice};
+use crate::rpc::Rpc;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const DEFAULT_MAX_STALENESS_SLOTS: u64 = 50;
+
+/// Oracle account pricing `mint` in USD
+#[derive(Debug, Clone, PartialEq)]
+pub struct OracleFeed {
+    pub mint: Pubkey,
+    pub kind: OracleKind,
+    pub account: Pubkey,
+}
+
+impl FromStr for OracleFeed {
+    type Err = anyhow::Error;
+
+    /// Parses `<mint>:<pyth|switchboard>:<account>`
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.trim().split(':').collect();
+        if parts.len() != 3 {
+            return Err(anyhow!("Invalid oracle feed: {}. Expected <mint>:<kind>:<account>", s));
+        }
+
+        Ok(Self {
+            mint: Pubkey::from_str(parts[0])?,
+            kind: parts[1].parse()?,
+            account: Pubkey::from_str(parts[2])?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OracleQuote {
+    pub price: OraclePrice,
+    pub age_slots: u64,
+    pub stale: bool,
+}
+
+/// Pseudo-venue quoting oracle prices in USD so they can be compared against
+/// AMM prices like any other DEX
+#[derive(Clone)]
+pub struct OracleDex {
+    feeds: Vec<OracleFeed>,
+    max_staleness_slots: u64,
+}
+
+impl OracleDex {
+    pub fn new(feeds: Vec<OracleFeed>) -> Self {
+        Self {
+            feeds,
+            max_staleness_slots: DEFAULT_MAX_STALENESS_SLOTS,
+        }
+    }
+
+    pub fn with_max_staleness_slots(mut self, max_staleness_slots: u64) -> Self {
+        self.max_staleness_slots = max_staleness_slots;
+        self
+    }
+
+    pub fn feeds(&self) -> &[OracleFeed] {
+        &self.feeds
+    }
+
+    pub async fn get_oracle_quote(
+        &self,
+        rpc_client: Arc<dyn Rpc>,
+        token_mint: &str,
+    ) -> Result<Option<OracleQuote>> {
+        let mint = Pubkey::from_str(token_mint)?;
+        let feed = match self.feeds.iter().find(|feed| feed.mint == mint) {
+            Some(feed) => feed,
+            None => return Ok(None),
+        };
+
+        let price = oracle::fetch_oracle_price(rpc_client.clone(), feed.kind, &feed.account).await?;
+        let current_slot = rpc_client.get_slot()?;
+        let age_slots = current_slot.saturating_sub(price.publish_slot);
+        let stale = age_slots > self.max_staleness_slots;
+
+        Ok(Some(OracleQuote { price, age_slots, stale }))
+    }
+}
+
+#[async_trait]
+impl DexProtocol for OracleDex {
+    fn name(&self) -> &str {
+        "Oracle"
+    }
+
+    async fn get_token_price(&self, rpc_client: Arc<dyn Rpc>, token_mint: &str) -> Result<PoolPrice, DexError> {
+        // Stale oracle prices are only surfaced through deviation reports
+        match self.get_oracle_quote(rpc_client, token_mint).await? {
+            Some(quote) if quote.stale => Err(DexError::Stale {
+                age_slots: quote.age_slots,
+                max_slots: self.max_staleness_slots,
+            }),
+            Some(quote) => Ok(PoolPrice {
+                price: quote.price.price,
+                quote_mint: mints::USDC.pubkey(),
+                liquidity: None,
+            }),
+            None => Err(DexError::pool_not_found(token_mint)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOL_USD_PYTH: &str = "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG";
+
+    #[test]
+    fn parses_oracle_feed() {
+        let feed: OracleFeed = format!(" {}:Pyth:{} ", mints::WSOL.address, SOL_USD_PYTH).parse().unwrap();
+
+        assert_eq!(feed.mint, mints::WSOL.pubkey());
+        assert_eq!(feed.kind, OracleKind::Pyth);
+        assert_eq!(feed.account.to_string(), SOL_USD_PYTH);
+    }
+
+    #[test]
+    fn rejects_malformed_oracle_feed() {
+        assert!(format!("{}:pyth", mints::WSOL.address).parse::<OracleFeed>().is_err());
+        assert!(format!("{}:chainlink:{}", mints::WSOL.address, SOL_USD_PYTH).parse::<OracleFeed>().is_err());
+        assert!(format!("not-a-mint:pyth:{}", SOL_USD_PYTH).parse::<OracleFeed>().is_err());
+    }
+}
@@ -1,81 +1,131 @@
-use super::DexProtocol;
-use anyhow::Result;
+use super::error::parse_mint;
+use super::{DexError, DexProtocol, PoolInfo, PoolPrice};
+use crate::mints;
+use crate::quote::QuoteAsset;
 use async_trait::async_trait;
-use solana_client::rpc_client::RpcClient;
+use crate::rpc::Rpc;
+use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
 use std::str::FromStr;
 use solana_client::rpc_filter::{RpcFilterType, Memcmp};
-use solana_client::rpc_config::RpcProgramAccountsConfig;
 
-const ORCA_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+// Whirlpool account layout, after the 8 byte Anchor discriminator. The
+// account holds no decimals, those come from the mint accounts.
+const LIQUIDITY_OFFSET: usize = 49; // u128
+const SQRT_PRICE_OFFSET: usize = 65; // u128, Q64.64
+const TOKEN_MINT_A_OFFSET: usize = 101;
+const TOKEN_MINT_B_OFFSET: usize = 181;
+const WHIRLPOOL_SIZE: usize = 653;
 
+/// Orca Whirlpools, priced from the current sqrt price
 #[derive(Clone)]
-pub struct OrcaDex;
+pub struct OrcaDex {
+    program_id: Pubkey,
+}
 
 impl OrcaDex {
-    pub fn new() -> Self {
-        Self
+    pub fn new(program_id: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            program_id: Pubkey::from_str(program_id)?,
+        })
     }
 
     async fn get_pool_price(
         &self,
-        rpc_client: Arc<RpcClient>,
+        rpc_client: Arc<dyn Rpc>,
         token_mint: &str,
-    ) -> anyhow::Result<Option<f64>> {
-        let program_id = Pubkey::from_str(ORCA_PROGRAM_ID)?;
-        let token_mint_pubkey = Pubkey::from_str(token_mint)?;
-
-        // Filter for pools containing our token
-        let filters = vec![
-            RpcFilterType::DataSize(1328), // Whirlpool account size
-            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
-                8, // offset for token A mint
-                token_mint_pubkey.to_bytes().to_vec(),
-            )),
-        ];
-
-        let config = RpcProgramAccountsConfig {
-            filters: Some(filters),
-            account_config: solana_client::rpc_config::RpcAccountInfoConfig {
-                encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-
-        let accounts = rpc_client.get_program_accounts_with_config(&program_id, config)?;
+    ) -> Result<PoolPrice, DexError> {
+        let token_mint_pubkey = parse_mint(token_mint)?;
 
         let mut best_pool = None;
         let mut max_liquidity = 0u128;
+        let mut decode_error = None;
+
+        // The token can sit on either side of a whirlpool; the other side has
+        // to be a quote asset for the price to be usable
+        for (token_offset, other_offset) in [
+            (TOKEN_MINT_A_OFFSET, TOKEN_MINT_B_OFFSET),
+            (TOKEN_MINT_B_OFFSET, TOKEN_MINT_A_OFFSET),
+        ] {
+            let filters = vec![
+                RpcFilterType::DataSize(WHIRLPOOL_SIZE as u64),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(token_offset, token_mint_pubkey.to_bytes().to_vec())),
+            ];
+
+            for (_, account) in rpc_client.get_program_accounts(&self.program_id, filters)? {
+                if account.data.len() < WHIRLPOOL_SIZE {
+                    decode_error = Some(DexError::DecodeError {
+                        expected_len: WHIRLPOOL_SIZE,
+                        actual_len: account.data.len(),
+                    });
+                    continue;
+                }
+
+                let other_mint = read_pubkey(&account.data, other_offset)?;
+                if QuoteAsset::from_mint(&other_mint).is_none() {
+                    continue;
+                }
 
-        for (_, account) in accounts {
-            if account.data.len() >= 1328 {
-                // Parse liquidity from bytes
-                let liquidity = u128::from_le_bytes(account.data[1224..1240].try_into()?);
-                
+                let liquidity = read_u128(&account.data, LIQUIDITY_OFFSET)?;
                 if liquidity > max_liquidity {
                     max_liquidity = liquidity;
-                    best_pool = Some(account);
+                    best_pool = Some((account, token_offset == TOKEN_MINT_B_OFFSET));
                 }
             }
         }
 
-        if let Some(pool) = best_pool {
-            // Parse sqrt_price from bytes
-            let sqrt_price = u128::from_le_bytes(pool.data[1240..1256].try_into()?);
-            let price = (sqrt_price as f64 * sqrt_price as f64) / (1u128 << 128) as f64;
-            
-            // Get token decimals
-            let token_a_decimals = pool.data[1264] as i32;
-            let token_b_decimals = pool.data[1265] as i32;
-            let decimal_adjustment = 10f64.powi(token_b_decimals - token_a_decimals);
-            
-            Ok(Some(price * decimal_adjustment))
-        } else {
-            Ok(None)
+        match (best_pool, decode_error) {
+            (Some((pool, token_is_b)), _) => {
+                let pool_price = Self::price_from_whirlpool(rpc_client.as_ref(), &pool.data)?;
+                if !token_is_b {
+                    return Ok(pool_price);
+                }
+                if pool_price.price <= 0.0 {
+                    return Err(DexError::Other(anyhow::anyhow!("Whirlpool has a zero price")));
+                }
+                // Priced as A per B instead
+                Ok(PoolPrice {
+                    price: 1.0 / pool_price.price,
+                    quote_mint: read_pubkey(&pool.data, TOKEN_MINT_A_OFFSET)?,
+                    liquidity: None,
+                })
+            }
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(DexError::pool_not_found(token_mint)),
         }
     }
+
+    fn price_from_whirlpool(rpc_client: &dyn Rpc, data: &[u8]) -> anyhow::Result<PoolPrice> {
+        let sqrt_price = read_u128(data, SQRT_PRICE_OFFSET)?;
+        let price = (sqrt_price as f64 * sqrt_price as f64) / 2f64.powi(128);
+
+        let token_mint_a = read_pubkey(data, TOKEN_MINT_A_OFFSET)?;
+        let token_mint_b = read_pubkey(data, TOKEN_MINT_B_OFFSET)?;
+
+        // The sqrt price is in base units, so scale by the decimals A has
+        // over B. Decimals don't depend on the epoch, only the transfer fee does.
+        let token_a_decimals = mints::fetch_mint_info(rpc_client, &token_mint_a, 0)?.decimals as i32;
+        let token_b_decimals = mints::fetch_mint_info(rpc_client, &token_mint_b, 0)?.decimals as i32;
+        let decimal_adjustment = 10f64.powi(token_a_decimals - token_b_decimals);
+
+        // Whirlpool prices are token B per token A. Liquidity is concentrated
+        // around the current tick and doesn't map to reserves without reading
+        // the vaults.
+        Ok(PoolPrice {
+            price: price * decimal_adjustment,
+            quote_mint: token_mint_b,
+            liquidity: None,
+        })
+    }
+}
+
+fn read_u128(data: &[u8], offset: usize) -> anyhow::Result<u128> {
+    Ok(u128::from_le_bytes(data[offset..offset + 16].try_into()?))
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> anyhow::Result<Pubkey> {
+    Ok(Pubkey::try_from(&data[offset..offset + 32])?)
 }
 
 #[async_trait]
@@ -84,11 +134,25 @@ impl DexProtocol for OrcaDex {
         "Orca"
     }
 
-    fn clone_box(&self) -> Box<dyn DexProtocol + Send + Sync> {
-        Box::new(self.clone())
+    async fn get_token_price(&self, rpc_client: Arc<dyn Rpc>, token_mint: &str) -> Result<PoolPrice, DexError> {
+        self.get_pool_price(rpc_client, token_mint).await
     }
 
-    async fn get_token_price(&self, rpc_client: Arc<RpcClient>, token_mint: &str) -> anyhow::Result<Option<f64>> {
-        self.get_pool_price(rpc_client, token_mint).await
+    async fn decode_pool(&self, rpc_client: Arc<dyn Rpc>, address: &Pubkey, account: &Account) -> anyhow::Result<Option<PoolInfo>> {
+        if account.owner != self.program_id || account.data.len() < WHIRLPOOL_SIZE {
+            return Ok(None);
+        }
+
+        let pool_price = Self::price_from_whirlpool(rpc_client.as_ref(), &account.data)?;
+        let base_mint = read_pubkey(&account.data, TOKEN_MINT_A_OFFSET)?;
+
+        Ok(Some(PoolInfo {
+            address: address.to_string(),
+            dex_name: self.name().to_string(),
+            base_mint: base_mint.to_string(),
+            quote_mint: pool_price.quote_mint.to_string(),
+            price: pool_price.price,
+            liquidity: pool_price.liquidity,
+        }))
     }
 } 
\ No newline at end of file
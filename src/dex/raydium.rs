@@ -1,7 +1,10 @@
-use super::DexProtocol;
+use super::error::parse_mint;
+use super::{DexError, DexProtocol, PoolInfo, PoolPrice};
+use crate::mints;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use solana_client::rpc_client::RpcClient;
+use crate::rpc::Rpc;
+use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
 use std::str::FromStr;
@@ -10,9 +13,11 @@ use spl_token_2022::amount_to_ui_amount;
 use common::common_utils;
 use solana_client::rpc_filter::{Memcmp, RpcFilterType};
 use std::time::Duration;
+use tracing::{debug, warn};
 
-const AMM_PROGRAM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+const AMM_INFO_SIZE: usize = 752;
 
+/// Raydium AMM v4 pools paired with WSOL
 #[derive(Clone)]
 pub struct RaydiumDex {
     program_id: Pubkey,
@@ -25,15 +30,48 @@ impl RaydiumDex {
         })
     }
 
-    async fn get_pool_price(
-        rpc_client: Arc<RpcClient>,
-        pool_id: Option<&str>,
-        mint: Option<&str>,
+    pub(crate) async fn get_pool_price(rpc_client: Arc<dyn Rpc>, pool_id: &Pubkey) -> Result<(f64, f64, f64)> {
+        let account = rpc_client.get_account(pool_id)?
+            .ok_or(anyhow!("NotFoundPool: pool state not found"))?;
+        let pool_state = Self::load_pool_state(&account.data)?;
+
+        Self::calculate_pool_price(rpc_client.as_ref(), pool_id, &pool_state)
+    }
+
+    async fn get_pool_price_by_mint(&self, rpc_client: Arc<dyn Rpc>, mint: &str) -> Result<(f64, f64, f64), DexError> {
+        let (amm_pool_id, pool_state) = Self::get_pool_state_by_mint(rpc_client.clone(), &self.program_id, mint).await?;
+
+        Ok(Self::calculate_pool_price(rpc_client.as_ref(), &amm_pool_id, &pool_state)?)
+    }
+
+    fn calculate_pool_price(
+        rpc_client: &dyn Rpc,
+        amm_pool_id: &Pubkey,
+        pool_state: &AmmInfo,
     ) -> Result<(f64, f64, f64)> {
-        let (amm_pool_id, pool_state) = Self::get_pool_state(rpc_client.clone(), pool_id, mint).await?;
-        
+        let (base_account, quote_account, price) = Self::get_pool_reserves(rpc_client, pool_state)?;
+
+        debug!(
+            pool = %amm_pool_id,
+            base_mint = %base_account.0,
+            base_amount = base_account.1,
+            quote_mint = %quote_account.0,
+            quote_amount = quote_account.1,
+            price,
+            "Calculated pool price"
+        );
+    
+        Ok((base_account.1, quote_account.1, price))
+    }
+
+    // Vault balances as ((base mint, amount), (quote mint, amount), price), with
+    // the SOL side as the quote when there is one
+    fn get_pool_reserves(
+        rpc_client: &dyn Rpc,
+        pool_state: &AmmInfo,
+    ) -> Result<((Pubkey, f64), (Pubkey, f64), f64)> {
         let load_pubkeys = vec![pool_state.pc_vault, pool_state.coin_vault];
-        let rsps = common::rpc::get_multiple_accounts(&rpc_client, &load_pubkeys)?;
+        let rsps = rpc_client.get_multiple_accounts(&load_pubkeys)?;
         
         // Add proper error handling for vault accounts
         let amm_pc_vault_account = rsps[0].clone()
@@ -70,86 +108,62 @@ impl RaydiumDex {
             )
         };
     
+        // An emptied pool has no price, and dividing would make one up
+        if base_account.1 == 0.0 || quote_account.1 == 0.0 {
+            return Err(anyhow!("Pool has an empty reserve"));
+        }
         let price = quote_account.1 / base_account.1;
-        
-        println!(
-            "calculate pool[{}]: {}: {}, {}: {}, price: {} sol",
-            amm_pool_id, base_account.0, base_account.1, quote_account.0, quote_account.1, price
-        );
-    
-        Ok((base_account.1, quote_account.1, price))
+
+        Ok((base_account, quote_account, price))
     }
 
-    async fn get_pool_state(
-        rpc_client: Arc<RpcClient>,
-        pool_id: Option<&str>,
-        mint: Option<&str>,
-    ) -> Result<(Pubkey, AmmInfo)> {
-        if let Some(pool_id) = pool_id {
-            let amm_pool_id = Pubkey::from_str(pool_id)?;
-            let account_data = common::rpc::get_account(&rpc_client, &amm_pool_id)?
-                .ok_or(anyhow!("NotFoundPool: pool state not found"))?;
-            
-            // Check if we're dealing with a v4 or v3 pool
-            let pool_state = if account_data.len() == 752 {
-                // V4 pool
-                AmmInfo::load_from_bytes(&account_data)?.to_owned()
-            } else if account_data.len() == 637 {
-                // V3 pool
-                let mut padded_data = vec![0u8; 752];
-                padded_data[..account_data.len()].copy_from_slice(&account_data);
-                AmmInfo::load_from_bytes(&padded_data)?.to_owned()
-            } else {
-                return Err(anyhow!(
-                    "Unexpected account data size: {}. Expected either 752 (v4) or 637 (v3)",
-                    account_data.len()
-                ));
-            };
-
-            Ok((amm_pool_id, pool_state))
-        } else if let Some(mint) = mint {
-            Self::get_pool_state_by_mint(rpc_client, mint).await
+    fn load_pool_state(account_data: &[u8]) -> Result<AmmInfo, DexError> {
+        let decode_error = DexError::DecodeError {
+            expected_len: AMM_INFO_SIZE,
+            actual_len: account_data.len(),
+        };
+
+        // Check if we're dealing with a v4 or v3 pool
+        if account_data.len() == AMM_INFO_SIZE {
+            // V4 pool
+            AmmInfo::load_from_bytes(account_data).map(|amm| amm.to_owned()).map_err(|_| decode_error)
+        } else if account_data.len() == 637 {
+            // V3 pool
+            let mut padded_data = vec![0u8; AMM_INFO_SIZE];
+            padded_data[..account_data.len()].copy_from_slice(account_data);
+            AmmInfo::load_from_bytes(&padded_data).map(|amm| amm.to_owned()).map_err(|_| decode_error)
         } else {
-            Err(anyhow!("NotFoundPool: pool state not found"))
+            Err(decode_error)
         }
     }
 
     async fn get_pool_state_by_mint(
-        rpc_client: Arc<RpcClient>,
+        rpc_client: Arc<dyn Rpc>,
+        amm_program: &Pubkey,
         mint: &str,
-    ) -> Result<(Pubkey, AmmInfo)> {
-        const AMM_INFO_SIZE: usize = 752;
+    ) -> Result<(Pubkey, AmmInfo), DexError> {
         const MAX_RETRIES: u32 = 3;
         const RETRY_DELAY: Duration = Duration::from_secs(1);
         
-        let pairs = vec![
-            (Some(spl_token::native_mint::ID), Pubkey::from_str(mint).ok()),
-            (Pubkey::from_str(mint).ok(), Some(spl_token::native_mint::ID)),
+        let mint_pubkey = parse_mint(mint)?;
+        let pairs = [
+            (mints::WSOL.pubkey(), mint_pubkey),
+            (mint_pubkey, mints::WSOL.pubkey()),
         ];
 
-        let amm_program = Pubkey::from_str(AMM_PROGRAM).expect("Failed to parse AMM program ID");
-        
+        // A failed orientation might have held the pool, so it isn't "not found"
+        let mut rpc_error = None;
+
         for (coin_mint, pc_mint) in pairs {
-            let filters = match (coin_mint, pc_mint) {
-                (None, None) => Some(vec![RpcFilterType::DataSize(AMM_INFO_SIZE as u64)]),
-                (Some(coin_mint), None) => Some(vec![
-                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(400, &coin_mint.to_bytes())),
-                    RpcFilterType::DataSize(AMM_INFO_SIZE as u64),
-                ]),
-                (None, Some(pc_mint)) => Some(vec![
-                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(432, &pc_mint.to_bytes())),
-                    RpcFilterType::DataSize(AMM_INFO_SIZE as u64),
-                ]),
-                (Some(coin_mint), Some(pc_mint)) => Some(vec![
-                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(400, &coin_mint.to_bytes())),
-                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(432, &pc_mint.to_bytes())),
-                    RpcFilterType::DataSize(AMM_INFO_SIZE as u64),
-                ]),
-            };
+            let filters = vec![
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(400, &coin_mint.to_bytes())),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(432, &pc_mint.to_bytes())),
+                RpcFilterType::DataSize(AMM_INFO_SIZE as u64),
+            ];
             
             // Add retry logic for RPC calls
             for retry in 0..MAX_RETRIES {
-                match common::rpc::get_program_accounts_with_filters(&rpc_client, amm_program, filters.clone()) {
+                match rpc_client.get_program_accounts(amm_program, filters.clone()) {
                     Ok(pools) => {
                         if !pools.is_empty() {
                             let pool = &pools[0];
@@ -163,18 +177,19 @@ impl RaydiumDex {
                     }
                     Err(e) => {
                         if retry < MAX_RETRIES - 1 {
-                            println!("RPC error (attempt {}/{}): {}. Retrying...", retry + 1, MAX_RETRIES, e);
+                            warn!(attempt = retry + 1, max_attempts = MAX_RETRIES, error = %e, "RPC error, retrying");
                             tokio::time::sleep(RETRY_DELAY).await;
                             continue;
                         } else {
-                            println!("Failed to get program accounts after {} attempts: {}", MAX_RETRIES, e);
+                            warn!(attempts = MAX_RETRIES, error = %e, "Failed to get program accounts");
+                            rpc_error = Some(DexError::from(e));
                         }
                     }
                 }
             }
         }
-        
-        Err(anyhow!("NotFoundPool: pool state not found"))
+
+        Err(rpc_error.unwrap_or_else(|| DexError::pool_not_found(mint)))
     }
 }
 
@@ -184,14 +199,33 @@ impl DexProtocol for RaydiumDex {
         "Raydium"
     }
 
-    fn clone_box(&self) -> Box<dyn DexProtocol + Send + Sync> {
-        Box::new(self.clone())
+    async fn get_token_price(&self, rpc_client: Arc<dyn Rpc>, token_mint: &str) -> Result<PoolPrice, DexError> {
+        // Pools are only searched against WSOL, so the quote side is always SOL
+        let (_base, quote, price) = self.get_pool_price_by_mint(rpc_client, token_mint).await?;
+
+        Ok(PoolPrice {
+            price,
+            quote_mint: mints::WSOL.pubkey(),
+            // Constant product pools hold equal value on both sides
+            liquidity: Some(quote * 2.0),
+        })
     }
 
-    async fn get_token_price(&self, rpc_client: Arc<RpcClient>, token_mint: &str) -> Result<Option<f64>> {
-        match Self::get_pool_price(rpc_client, None, Some(token_mint)).await {
-            Ok((_base, _quote, price)) => Ok(Some(price)),
-            Err(_) => Ok(None)
+    async fn decode_pool(&self, rpc_client: Arc<dyn Rpc>, address: &Pubkey, account: &Account) -> Result<Option<PoolInfo>> {
+        if account.owner != self.program_id {
+            return Ok(None);
         }
+
+        let pool_state = Self::load_pool_state(&account.data)?;
+        let ((base_mint, _), (quote_mint, quote), price) = Self::get_pool_reserves(rpc_client.as_ref(), &pool_state)?;
+
+        Ok(Some(PoolInfo {
+            address: address.to_string(),
+            dex_name: self.name().to_string(),
+            base_mint: base_mint.to_string(),
+            quote_mint: quote_mint.to_string(),
+            price,
+            liquidity: Some(quote * 2.0),
+        }))
     }
 } 
\ No newline at end of file
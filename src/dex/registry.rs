@@ -0,0 +1,124 @@
+use super::DexProtocol;
+use crate::cluster::Cluster;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[cfg(feature = "meteora")]
+use super::meteora::MeteoraDex;
+#[cfg(feature = "orca")]
+use super::orca::OrcaDex;
+#[cfg(feature = "raydium")]
+use super::raydium::RaydiumDex;
+
+/// Names of the adapters this crate ships, whether or not the build includes them
+pub const BUILTIN_DEXES: [&str; 3] = ["raydium", "meteora", "orca"];
+
+/// What a factory gets to build its adapter from: the `[dexes.<name>]`
+/// section of the config
+#[derive(Debug, Clone, Copy)]
+pub struct DexSettings<'a> {
+    pub cluster: Cluster,
+    /// Unset means the adapter's deployment on `cluster`
+    pub program_id: Option<&'a str>,
+    /// Adapter-specific keys from `[dexes.<name>.options]`
+    pub options: &'a toml::Table,
+}
+
+pub type DexFactory = Box<dyn Fn(&DexSettings) -> Result<Arc<dyn DexProtocol>> + Send + Sync>;
+
+/// Adapters by config name. Starts out with the built-in venues this build
+/// includes; other crates add theirs with [`DexRegistry::register`].
+///
+/// ```no_run
+/// use async_trait::async_trait;
+/// use raytx::dex::{DexError, DexProtocol, DexRegistry, PoolPrice};
+/// use raytx::rpc::Rpc;
+/// use std::sync::Arc;
+///
+/// struct Phoenix {
+///     program_id: String,
+/// }
+///
+/// #[async_trait]
+/// impl DexProtocol for Phoenix {
+///     fn name(&self) -> &str {
+///         "Phoenix"
+///     }
+///
+///     async fn get_token_price(&self, _rpc_client: Arc<dyn Rpc>, token_mint: &str) -> Result<PoolPrice, DexError> {
+///         Err(DexError::pool_not_found(token_mint))
+///     }
+/// }
+///
+/// let mut registry = DexRegistry::default();
+/// registry.register("phoenix", |settings| {
+///     let program_id = settings.program_id.unwrap_or("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY");
+///     Ok(Arc::new(Phoenix { program_id: program_id.to_string() }))
+/// });
+/// ```
+pub struct DexRegistry {
+    factories: BTreeMap<String, DexFactory>,
+}
+
+impl DexRegistry {
+    /// A registry without any adapters
+    pub fn empty() -> Self {
+        Self {
+            factories: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a factory under `name`, replacing any adapter registered
+    /// under the same name. Names are matched case-insensitively.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&DexSettings) -> Result<Arc<dyn DexProtocol>> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.to_lowercase(), Box::new(factory));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(&name.to_lowercase())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    pub fn create(&self, name: &str, settings: &DexSettings) -> Result<Arc<dyn DexProtocol>> {
+        let factory = self.factories.get(&name.to_lowercase()).ok_or_else(|| {
+            anyhow!(
+                "Unknown DEX {}. Expected one of {}",
+                name,
+                self.names().collect::<Vec<_>>().join(", ")
+            )
+        })?;
+        factory(settings)
+    }
+}
+
+impl Default for DexRegistry {
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::empty();
+
+        #[cfg(feature = "raydium")]
+        registry.register("raydium", |settings| {
+            let program_id = settings.program_id.unwrap_or(settings.cluster.raydium_program_id());
+            Ok(Arc::new(RaydiumDex::new(program_id)?))
+        });
+        #[cfg(feature = "meteora")]
+        registry.register("meteora", |settings| {
+            let program_id = settings.program_id.unwrap_or(settings.cluster.meteora_program_id());
+            Ok(Arc::new(MeteoraDex::new(program_id)?))
+        });
+        #[cfg(feature = "orca")]
+        registry.register("orca", |settings| {
+            let program_id = settings.program_id.unwrap_or(settings.cluster.orca_program_id());
+            Ok(Arc::new(OrcaDex::new(program_id)?))
+        });
+
+        registry
+    }
+}
@@ -0,0 +1,58 @@
+//! Reads Solana token prices straight from on-chain pools and oracle feeds
+//! and compares them across venues.
+//!
+//! [`dex`] holds the [`DexProtocol`] trait, one adapter per venue and the
+//! price and opportunity types every adapter returns. [`PriceFetcher`] runs
+//! a set of adapters over a token list, normalizes their prices to one quote
+//! asset ([`quote`]) and finds spreads between venues. [`DexRegistry`] builds
+//! adapters by config name, and other crates can register venues of their
+//! own. Adapters read the chain through [`rpc::Rpc`], which a recorded
+//! [`rpc::Fixture`] can stand in for. [`output`] writes prices and
+//! opportunities to files and databases and reads them back. The `raytx`
+//! binary is built on this API alone.
+//!
+//! # Features
+//!
+//! `raydium`, `meteora` and `orca` each compile one adapter and are all on by
+//! default. Oracle feeds are always available. Without `raydium`, SOL/USD
+//! comes from the fallback oracle only. `parquet` and `postgres` add those
+//! output sinks.
+//!
+//! ```toml
+//! raytx = { git = "...", default-features = false, features = ["orca"] }
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! use raytx::cluster::Cluster;
+//! use raytx::dex::orca::OrcaDex;
+//! use raytx::{metrics, PriceFetcher};
+//! use std::sync::Arc;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let rpc_client = Arc::new(metrics::rpc_client(Cluster::Mainnet.rpc_url()));
+//! let mut price_fetcher = PriceFetcher::new(rpc_client);
+//! price_fetcher.add_dex(Arc::new(OrcaDex::new(Cluster::Mainnet.orca_program_id())?));
+//!
+//! for price in price_fetcher.fetch_token_prices("So11111111111111111111111111111111111111112").await? {
+//!     println!("{} {} {}", price.dex_name, price.price, price.quote_mint);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod cluster;
+pub mod dex;
+pub mod logging;
+pub mod metrics;
+pub mod mints;
+pub mod oracle;
+pub mod output;
+pub mod price_fetcher;
+pub mod quote;
+pub mod rpc;
+
+pub use dex::{ArbitrageOpportunity, DexError, DexProtocol, DexRegistry, PoolInfo, PoolPrice, TokenPrice};
+pub use price_fetcher::{PriceFetcher, ScanResult};
+pub use quote::QuoteAsset;
@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Result};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::io::{self, IsTerminal, Write};
+use std::str::FromStr;
+use std::sync::LazyLock;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+// Every visible progress bar is drawn through this, so log lines can clear
+// the bars before printing instead of interleaving with them
+static PROGRESS: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(anyhow!("Unknown log format: {}. Expected text or json", s)),
+        }
+    }
+}
+
+/// Hidden unless stdout is a terminal, so piped output and service logs stay clean
+pub fn progress_bar(len: u64) -> ProgressBar {
+    if !io::stdout().is_terminal() {
+        return ProgressBar::hidden();
+    }
+
+    let pb = PROGRESS.add(ProgressBar::new(len));
+    pb.set_style(ProgressStyle::default_bar()
+        .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
+        .expect("Failed to set progress bar style"));
+    pb
+}
+
+struct StderrWriter;
+
+impl Write for StderrWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        PROGRESS.suspend(|| io::stderr().write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for StderrWriter {
+    type Writer = StderrWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        StderrWriter
+    }
+}
+
+/// `level` takes EnvFilter directives, e.g. "info" or "info,raytx::dex=debug".
+/// Logs always go to stderr; stdout is reserved for results.
+pub fn init(level: &str, format: LogFormat) -> Result<()> {
+    let filter = EnvFilter::try_new(level).map_err(|e| anyhow!("Invalid log level {}: {}", level, e))?;
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(StderrWriter);
+
+    match format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().try_init(),
+    }
+    .map_err(|e| anyhow!("Failed to initialize logging: {}", e))
+}
@@ -1,80 +1,403 @@
-mod dex;
-mod price_fetcher;
+mod alerts;
+mod api;
+mod cli;
+mod config;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::time;
-use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
 use std::sync::Arc;
+use clap::Parser;
 use dotenv::dotenv;
+use serde::Serialize;
 use std::time::Instant;
-use crate::price_fetcher::PriceFetcher;
-use crate::dex::raydium::RaydiumDex;
-use crate::dex::DexType;
-use crate::dex::meteora::MeteoraDex;
-use crate::dex::orca::OrcaDex;
+use crate::alerts::telegram::Telegram;
+use crate::alerts::Alerter;
+use crate::api::ApiState;
+use crate::cli::{Cli, Command, DexArgs, Format, WatchArgs};
+use crate::config::{Config, ConfigWatcher, OutputConfig};
+use raytx::output::{OutputSink, SinkOptions};
+use raytx::price_fetcher::PriceFetcher;
+use raytx::dex::oracle::OracleDex;
+use raytx::dex::registry::BUILTIN_DEXES;
+use raytx::dex::{DexRegistry, DexSettings};
+use raytx::rpc::{FixtureRpc, RecordingRpc, Rpc};
+use raytx::{logging, metrics};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
-    
-    println!("Starting multi-DEX arbitrage finder...");
-    
-    let rpc_url = std::env::var("RPC_URL").context("RPC_URL not set")?;
-    let rpc_client = Arc::new(RpcClient::new(rpc_url));
-    
+
+    let Cli { config: config_path, dex, format, record_fixture, replay_fixture, command } = Cli::parse();
+
+    // No subcommand keeps the old behaviour: watch, configured from the environment
+    let command = command.unwrap_or_else(|| Command::Watch(Box::new(WatchArgs::parse_from(["watch"]))));
+    if matches!(command, Command::Watch(_)) && (record_fixture.is_some() || replay_fixture.is_some()) {
+        bail!("--record-fixture and --replay-fixture only work with price, pool and scan");
+    }
+
+    let config_path = Config::resolve_path(config_path.as_deref());
+    let config = load_config(config_path.as_deref(), &dex, &command)?;
+    logging::init(&config.log.level, config.log.format)?;
+
+    let rpc_client = match &replay_fixture {
+        Some(path) => Arc::new(FixtureRpc::load(path)?),
+        None => node_rpc(&config),
+    };
+    let recorder = record_fixture.as_ref().map(|_| Arc::new(RecordingRpc::new(rpc_client.clone())));
+    let rpc_client = match &recorder {
+        Some(recorder) => recorder.clone(),
+        None => rpc_client,
+    };
+
+    let result = match &command {
+        Command::Price { mint } => run_price(&build_price_fetcher(&config, rpc_client)?, mint, format).await,
+        Command::Pool { address } => run_pool(&build_price_fetcher(&config, rpc_client)?, address, format).await,
+        Command::Scan(_) => run_scan(&build_price_fetcher(&config, rpc_client)?, &config, format).await,
+        Command::Watch(_) => run_watch(config, config_path, &dex, &command, format).await,
+    };
+
+    // Saved even when the command failed, so the failure can be replayed
+    if let (Some(recorder), Some(path)) = (recorder, &record_fixture) {
+        recorder.save(path)?;
+        info!(path = %path.display(), "Recorded RPC fixture");
+    }
+
+    result
+}
+
+// The config file with env and flag overrides layered on top
+fn load_config(path: Option<&Path>, dex: &DexArgs, command: &Command) -> Result<Config> {
+    let mut config = Config::load(path)?;
+    dex.apply(&mut config)?;
+    command.apply(&mut config);
+    config.validate()?;
+    Ok(config)
+}
+
+fn node_rpc(config: &Config) -> Arc<dyn Rpc> {
+    Arc::new(metrics::rpc_client(config.rpc_url()))
+}
+
+fn build_price_fetcher(config: &Config, rpc_client: Arc<dyn Rpc>) -> Result<PriceFetcher> {
     let mut price_fetcher = PriceFetcher::new(rpc_client);
+    price_fetcher.set_quote_asset(config.quote.asset);
+    price_fetcher.set_token_sources(&config.tokens.source_url, config.tokens.watchlist.clone());
+
+    let usd_reference_pool = config
+        .usd_reference_pool()
+        .map(Pubkey::from_str)
+        .transpose()
+        .context("Failed to parse USD_REFERENCE_POOL")?;
+    let usd_fallback_oracle = config
+        .usd_fallback_oracle()
+        .map(Pubkey::from_str)
+        .transpose()
+        .context("Failed to parse USD_FALLBACK_ORACLE")?;
+    price_fetcher.set_usd_view(
+        config.quote.usd_view,
+        usd_reference_pool,
+        usd_fallback_oracle,
+        config.quote.trade_size_usd,
+    );
     
-    // Add DEXes
-    let raydium_program_id = std::env::var("RAYDIUM_PROGRAM_ID")
-        .context("RAYDIUM_PROGRAM_ID not set")?;
-    price_fetcher.add_dex(DexType::Raydium(RaydiumDex::new(&raydium_program_id)?));
+    // Built-in venues left out of the build are skipped with a warning, so
+    // one config works for every build
+    let registry = DexRegistry::default();
+    for (name, dex) in config.dexes.enabled() {
+        if !registry.contains(name) && BUILTIN_DEXES.contains(&name) {
+            warn!(dex = name, "Skipping a DEX this build leaves out");
+            continue;
+        }
 
-    let meteora_program_id = std::env::var("METEORA_PROGRAM_ID")
-        .ok()
-        .map(|s| s.to_string());
-    let meteora_program_id = meteora_program_id.as_deref();
-    price_fetcher.add_dex(DexType::Meteora(MeteoraDex::new(meteora_program_id)?));
+        let settings = DexSettings {
+            cluster: config.rpc.cluster,
+            program_id: dex.program_id.as_deref(),
+            options: &dex.options,
+        };
+        let dex = registry.create(name, &settings).with_context(|| format!("Failed to set up DEX {}", name))?;
+        price_fetcher.add_dex(dex);
+    }
 
-    // Add Orca DEX - simplified initialization
-    price_fetcher.add_dex(DexType::Orca(OrcaDex::new()));
+    // Oracle pseudo-venue, defaults to the cluster's Pyth SOL/USD feed
+    let oracle_feeds = config.oracle_feeds()?;
+    if !oracle_feeds.is_empty() {
+        price_fetcher.set_oracle(OracleDex::new(oracle_feeds).with_max_staleness_slots(config.oracle.max_staleness_slots));
+    }
 
-    let update_interval = std::env::var("UPDATE_INTERVAL")
-        .unwrap_or_else(|_| "300".to_string())
-        .parse::<u64>()
-        .context("Failed to parse UPDATE_INTERVAL")?;
+    Ok(price_fetcher)
+}
 
-    let min_price_difference = std::env::var("MIN_PRICE_DIFFERENCE")
-        .unwrap_or_else(|_| "1.0".to_string())
-        .parse::<f64>()
-        .context("Failed to parse MIN_PRICE_DIFFERENCE")?;
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
 
-    let data_dir = Path::new("data");
-    fs::create_dir_all(data_dir).context("Failed to create data directory")?;
+async fn run_price(price_fetcher: &PriceFetcher, mint: &str, format: Format) -> Result<()> {
+    let prices = price_fetcher.fetch_token_prices(mint).await?;
 
-    let mut interval = time::interval(Duration::from_secs(update_interval));
+    match format {
+        Format::Json => print_json(&prices)?,
+        Format::Text => {
+            for price in &prices {
+                print!("{}", PriceFetcher::format_price(price));
+            }
+            if prices.is_empty() {
+                println!("No pools found for {}", mint);
+            }
+        }
+    }
 
-    loop {
+    Ok(())
+}
+
+async fn run_pool(price_fetcher: &PriceFetcher, address: &Pubkey, format: Format) -> Result<()> {
+    let pool = price_fetcher.decode_pool(address).await?;
+
+    match format {
+        Format::Json => print_json(&pool)?,
+        Format::Text => print!("{}", PriceFetcher::format_pool(&pool)),
+    }
+
+    Ok(())
+}
+
+async fn run_scan(price_fetcher: &PriceFetcher, config: &Config, format: Format) -> Result<()> {
+    let start = Instant::now();
+    let scan = price_fetcher.scan(config.thresholds.min_price_difference).await?;
+    let deviations = price_fetcher.find_oracle_deviations(config.thresholds.max_oracle_deviation).await?;
+    scan.errors.log();
+
+    match format {
+        Format::Json => print_json(&serde_json::json!({
+            "prices": scan.prices,
+            "opportunities": scan.opportunities,
+            "oracle_deviations": deviations,
+            "errors": scan.errors,
+        }))?,
+        Format::Text => {
+            for opportunity in &scan.opportunities {
+                print!("{}", PriceFetcher::format_arbitrage_opportunity(opportunity));
+            }
+            for deviation in &deviations {
+                print!("{}", PriceFetcher::format_oracle_deviation(deviation));
+            }
+            println!("Found {} arbitrage opportunities in {:?}", scan.opportunities.len(), start.elapsed());
+        }
+    }
+
+    Ok(())
+}
+
+fn create_sinks(output: &OutputConfig) -> Result<Vec<Box<dyn OutputSink>>> {
+    fs::create_dir_all(&output.data_dir).context("Failed to create data directory")?;
+
+    let sink_options = SinkOptions {
+        data_dir: output.data_dir.clone(),
+        rolling: output.rolling,
+        postgres_url: output.postgres_url.clone(),
+        timescale: output.postgres_timescale,
+        scanner_id: output.scanner_id.clone(),
+        rotation: output.rotation.policy(),
+    };
+
+    output.formats
+        .iter()
+        .map(|format| format.create_sink(&sink_options))
+        .collect()
+}
+
+// Everything a watch cycle runs with, swapped as a whole when the config changes
+struct WatchState {
+    config: Config,
+    price_fetcher: PriceFetcher,
+    sinks: Vec<Box<dyn OutputSink>>,
+    alerter: Alerter,
+}
+
+impl WatchState {
+    fn new(config: Config) -> Result<Self> {
+        Ok(Self {
+            price_fetcher: build_price_fetcher(&config, node_rpc(&config))?,
+            sinks: create_sinks(&config.output)?,
+            alerter: Alerter::new(&config.alerts)?,
+            config,
+        })
+    }
+
+    // Builds the new fetcher and sinks before touching the running ones, so a
+    // failure leaves the previous config in place
+    fn reconfigure(&mut self, config: Config) -> Result<()> {
+        let price_fetcher = build_price_fetcher(&config, node_rpc(&config))?;
+        let sinks = if config.output != self.config.output {
+            Some(create_sinks(&config.output)?)
+        } else {
+            None
+        };
+        let notifiers = if config.alerts.webhooks != self.config.alerts.webhooks {
+            Some(alerts::create_notifiers(&config.alerts)?)
+        } else {
+            None
+        };
+
+        self.price_fetcher = price_fetcher;
+        if let Some(sinks) = sinks {
+            self.sinks = sinks;
+        }
+        if let Some(notifiers) = notifiers {
+            self.alerter.set_notifiers(notifiers);
+        }
+        self.config = config;
+        Ok(())
+    }
+
+    // Scans once, then hands the results to the sinks, the API and alerting
+    async fn run_cycle(&mut self, api: &ApiState, format: Format) {
         let start = Instant::now();
-        interval.tick().await;
-        
-        match price_fetcher.find_arbitrage_opportunities(min_price_difference).await {
-            Ok(opportunities) => {
-                let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-                let arb_file = data_dir.join(format!("arbitrage_opportunities_{}.log", timestamp));
-                
-                if let Err(e) = PriceFetcher::save_arbitrage_opportunities(&opportunities, &arb_file).await {
-                    eprintln!("Error saving arbitrage opportunities: {}", e);
+        let WatchState { config, price_fetcher, sinks, alerter } = self;
+
+        match price_fetcher.scan(config.thresholds.min_price_difference).await {
+            Ok(scan) => {
+                scan.errors.log();
+
+                for sink in sinks.iter_mut() {
+                    if let Err(e) = sink.write_prices(&scan.prices).await {
+                        error!(sink = sink.name(), error = %e, "Error saving prices");
+                    }
+                    if let Err(e) = sink.write_opportunities(&scan.opportunities).await {
+                        error!(sink = sink.name(), error = %e, "Error saving arbitrage opportunities");
+                    }
                 }
 
+                api.publish(&scan, config.thresholds.update_interval_secs).await;
+
+                alerter.process(&config.alerts, &scan);
+
                 let duration = start.elapsed();
-                println!("Found {} arbitrage opportunities in {:?}", opportunities.len(), duration);
+                metrics::record_cycle(duration, scan.opportunities.len());
+                debug!(
+                    opportunities = scan.opportunities.len(),
+                    prices = scan.prices.len(),
+                    elapsed_ms = duration.as_millis() as u64,
+                    "Cycle complete"
+                );
+                match format {
+                    Format::Json => println!("{}", serde_json::json!({
+                        "opportunities": scan.opportunities,
+                        "errors": scan.errors,
+                        "elapsed_ms": duration.as_millis() as u64,
+                    })),
+                    Format::Text => {
+                        for opportunity in &scan.opportunities {
+                            print!("{}", PriceFetcher::format_arbitrage_opportunity(opportunity));
+                        }
+                        println!("Found {} arbitrage opportunities in {:?}", scan.opportunities.len(), duration);
+                    }
+                }
+            }
+            Err(e) => {
+                metrics::record_failed_cycle();
+                error!(error = %e, "Error finding arbitrage opportunities");
+            }
+        }
+
+        match price_fetcher.find_oracle_deviations(config.thresholds.max_oracle_deviation).await {
+            Ok(deviations) => {
+                for sink in sinks.iter_mut() {
+                    if let Err(e) = sink.write_deviations(&deviations).await {
+                        error!(sink = sink.name(), error = %e, "Error saving oracle deviations");
+                    }
+                }
+                if format == Format::Text {
+                    for deviation in &deviations {
+                        print!("{}", PriceFetcher::format_oracle_deviation(deviation));
+                    }
+                }
             }
             Err(e) => {
-                eprintln!("Error finding arbitrage opportunities: {}", e);
+                error!(error = %e, "Error finding oracle deviations");
+            }
+        }
+    }
+
+    // Returns whether the update interval changed
+    fn reload(&mut self, path: &Path, dex: &DexArgs, command: &Command) -> bool {
+        let config = match load_config(Some(path), dex, command) {
+            Ok(config) => config,
+            Err(e) => {
+                error!(path = %path.display(), error = %format_args!("{:#}", e), "Keeping the previous config, reload failed");
+                return false;
+            }
+        };
+
+        let changes = self.config.changes(&config);
+        if changes.is_empty() {
+            return false;
+        }
+
+        let interval_changed = config.thresholds.update_interval_secs != self.config.thresholds.update_interval_secs;
+        if config.api != self.config.api
+            || config.log != self.config.log
+            || config.alerts.telegram != self.config.alerts.telegram
+        {
+            warn!(path = %path.display(), "api, log and telegram changes take effect after a restart");
+        }
+        if let Err(e) = self.reconfigure(config) {
+            error!(path = %path.display(), error = %format_args!("{:#}", e), "Keeping the previous config, new one could not be applied");
+            return false;
+        }
+
+        for change in &changes {
+            info!(path = %path.display(), change = %change, "Reloaded config");
+        }
+        interval_changed
+    }
+}
+
+fn cycle_interval(config: &Config) -> time::Interval {
+    let period = Duration::from_secs(config.thresholds.update_interval_secs);
+    time::interval_at(time::Instant::now() + period, period)
+}
+
+async fn run_watch(
+    config: Config,
+    config_path: Option<PathBuf>,
+    dex: &DexArgs,
+    command: &Command,
+    format: Format,
+) -> Result<()> {
+    info!(cluster = %config.rpc.cluster, "Starting multi-DEX arbitrage finder");
+
+    let api = ApiState::new();
+    if config.api.enabled {
+        api::serve(&config.api.listen, api.clone()).await?;
+    }
+
+    let mut state = WatchState::new(config)?;
+    if let Some(telegram) = &state.config.alerts.telegram {
+        let bot = Arc::new(Telegram::new(telegram, api.clone())?);
+        bot.clone().spawn_commands();
+        state.alerter.add_bot(bot);
+    }
+    let mut config_watcher = config_path.as_deref().map(ConfigWatcher::new);
+    let mut interval = time::interval(Duration::from_secs(state.config.thresholds.update_interval_secs));
+    let mut cycle: u64 = 0;
+
+    loop {
+        interval.tick().await;
+
+        if let Some(config_watcher) = config_watcher.as_mut() {
+            if config_watcher.changed() && state.reload(config_watcher.path(), dex, command) {
+                interval = cycle_interval(&state.config);
             }
         }
+
+        cycle += 1;
+        state.run_cycle(&api, format).instrument(info_span!("cycle", cycle)).await;
     }
 }
@@ -0,0 +1,129 @@
+use anyhow::{anyhow, Result};
+use crate::rpc::Rpc;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::state::Mint;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownMint {
+    pub symbol: &'static str,
+    pub address: &'static str,
+    pub decimals: u8,
+}
+
+impl KnownMint {
+    pub fn pubkey(&self) -> Pubkey {
+        Pubkey::from_str(self.address).expect("Invalid well-known mint address")
+    }
+}
+
+pub const WSOL: KnownMint = KnownMint {
+    symbol: "WSOL",
+    address: "So11111111111111111111111111111111111111112",
+    decimals: 9,
+};
+
+pub const USDC: KnownMint = KnownMint {
+    symbol: "USDC",
+    address: "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+    decimals: 6,
+};
+
+pub const USDT: KnownMint = KnownMint {
+    symbol: "USDT",
+    address: "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB",
+    decimals: 6,
+};
+
+pub const MSOL: KnownMint = KnownMint {
+    symbol: "mSOL",
+    address: "mSoLzYCxHdYgdzU16g5QSh3i5K4z3KZK7ytfqcJm7So",
+    decimals: 9,
+};
+
+pub const JITOSOL: KnownMint = KnownMint {
+    symbol: "jitoSOL",
+    address: "J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn",
+    decimals: 9,
+};
+
+pub const BSOL: KnownMint = KnownMint {
+    symbol: "bSOL",
+    address: "bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1",
+    decimals: 9,
+};
+
+pub const KNOWN_MINTS: &[KnownMint] = &[WSOL, USDC, USDT, MSOL, JITOSOL, BSOL];
+
+/// USD stablecoins treated as 1:1 with USD when normalizing quotes
+pub const USD_STABLECOINS: &[KnownMint] = &[USDC, USDT];
+
+pub fn find_by_address(address: &str) -> Option<&'static KnownMint> {
+    KNOWN_MINTS.iter().find(|mint| mint.address == address)
+}
+
+pub fn find_by_pubkey(mint: &Pubkey) -> Option<&'static KnownMint> {
+    find_by_address(&mint.to_string())
+}
+
+pub fn is_usd_stablecoin(mint: &Pubkey) -> bool {
+    USD_STABLECOINS.iter().any(|stable| stable.pubkey() == *mint)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFee {
+    pub basis_points: u16,
+    pub maximum_fee: u64,
+}
+
+impl TransferFee {
+    /// Fraction of a transfer withheld, ignoring the maximum fee cap
+    pub fn rate(&self) -> f64 {
+        self.basis_points as f64 / 10_000.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MintInfo {
+    pub decimals: u8,
+    pub transfer_fee: Option<TransferFee>,
+}
+
+/// Parses both SPL Token and Token-2022 mints, reading the transfer fee that
+/// applies in `epoch` when the TransferFeeConfig extension is present
+pub fn parse_mint(data: &[u8], epoch: u64) -> Result<MintInfo> {
+    let mint = StateWithExtensions::<Mint>::unpack(data)?;
+    let transfer_fee = mint
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .map(|config| {
+            let fee = config.get_epoch_fee(epoch);
+            TransferFee {
+                basis_points: u16::from(fee.transfer_fee_basis_points),
+                maximum_fee: u64::from(fee.maximum_fee),
+            }
+        })
+        .filter(|fee| fee.basis_points > 0);
+
+    Ok(MintInfo {
+        decimals: mint.base.decimals,
+        transfer_fee,
+    })
+}
+
+pub fn fetch_mint_info(rpc_client: &dyn Rpc, mint: &Pubkey, epoch: u64) -> Result<MintInfo> {
+    // Well-known mints are plain SPL Token mints without extensions
+    if let Some(known) = find_by_pubkey(mint) {
+        return Ok(MintInfo {
+            decimals: known.decimals,
+            transfer_fee: None,
+        });
+    }
+
+    let account = rpc_client
+        .get_account(mint)?
+        .ok_or_else(|| anyhow!("Mint account {} not found", mint))?;
+    parse_mint(&account.data, epoch)
+}
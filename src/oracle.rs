@@ -0,0 +1,262 @@
+use anyhow::{anyhow, Result};
+use crate::rpc::Rpc;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+
+// Offsets into the Pyth v2 price account layout
+const EXPO_OFFSET: usize = 20;
+const AGG_PRICE_OFFSET: usize = 208;
+const AGG_CONF_OFFSET: usize = 216;
+const AGG_STATUS_OFFSET: usize = 224;
+const AGG_PUB_SLOT_OFFSET: usize = 232;
+const PYTH_PRICE_MIN_SIZE: usize = 240;
+
+// Anchor discriminator of the Switchboard v2 AggregatorAccountData account
+const SWITCHBOARD_AGGREGATOR_DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+// Offsets into the packed AggregatorAccountData, inside latest_confirmed_round
+const ROUND_OPEN_SLOT_OFFSET: usize = 350;
+const RESULT_MANTISSA_OFFSET: usize = 366;
+const RESULT_SCALE_OFFSET: usize = 382;
+const STD_DEVIATION_MANTISSA_OFFSET: usize = 386;
+const STD_DEVIATION_SCALE_OFFSET: usize = 402;
+const SWITCHBOARD_AGGREGATOR_MIN_SIZE: usize = 406;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OracleKind {
+    Pyth,
+    Switchboard,
+}
+
+impl FromStr for OracleKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "pyth" => Ok(OracleKind::Pyth),
+            "switchboard" => Ok(OracleKind::Switchboard),
+            _ => Err(anyhow!("Unknown oracle kind: {}. Expected pyth or switchboard", s)),
+        }
+    }
+}
+
+/// Oracle price normalized across providers; `conf` is the provider's
+/// uncertainty (Pyth confidence, Switchboard standard deviation)
+#[derive(Debug, Clone, Copy)]
+pub struct OraclePrice {
+    pub price: f64,
+    pub conf: f64,
+    pub publish_slot: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PythPrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_slot: u64,
+}
+
+impl PythPrice {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < PYTH_PRICE_MIN_SIZE {
+            return Err(anyhow!("Data length too short for Pyth price account"));
+        }
+
+        let magic = u32::from_le_bytes(data[0..4].try_into()?);
+        let account_type = u32::from_le_bytes(data[8..12].try_into()?);
+        if magic != PYTH_MAGIC || account_type != PYTH_PRICE_ACCOUNT_TYPE {
+            return Err(anyhow!("Account is not a Pyth price account"));
+        }
+
+        let status = u32::from_le_bytes(data[AGG_STATUS_OFFSET..AGG_STATUS_OFFSET + 4].try_into()?);
+        if status != PYTH_STATUS_TRADING {
+            return Err(anyhow!("Pyth price is not trading (status {})", status));
+        }
+
+        Ok(Self {
+            price: i64::from_le_bytes(data[AGG_PRICE_OFFSET..AGG_PRICE_OFFSET + 8].try_into()?),
+            conf: u64::from_le_bytes(data[AGG_CONF_OFFSET..AGG_CONF_OFFSET + 8].try_into()?),
+            expo: i32::from_le_bytes(data[EXPO_OFFSET..EXPO_OFFSET + 4].try_into()?),
+            publish_slot: u64::from_le_bytes(data[AGG_PUB_SLOT_OFFSET..AGG_PUB_SLOT_OFFSET + 8].try_into()?),
+        })
+    }
+
+    pub fn ui_price(&self) -> f64 {
+        self.price as f64 * 10f64.powi(self.expo)
+    }
+
+    pub fn ui_conf(&self) -> f64 {
+        self.conf as f64 * 10f64.powi(self.expo)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SwitchboardDecimal {
+    pub mantissa: i128,
+    pub scale: u32,
+}
+
+impl SwitchboardDecimal {
+    fn read(data: &[u8], mantissa_offset: usize, scale_offset: usize) -> Result<Self> {
+        Ok(Self {
+            mantissa: i128::from_le_bytes(data[mantissa_offset..mantissa_offset + 16].try_into()?),
+            scale: u32::from_le_bytes(data[scale_offset..scale_offset + 4].try_into()?),
+        })
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SwitchboardAggregator {
+    pub result: SwitchboardDecimal,
+    pub std_deviation: SwitchboardDecimal,
+    pub round_open_slot: u64,
+}
+
+impl SwitchboardAggregator {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < SWITCHBOARD_AGGREGATOR_MIN_SIZE {
+            return Err(anyhow!("Data length too short for Switchboard aggregator account"));
+        }
+
+        if data[0..8] != SWITCHBOARD_AGGREGATOR_DISCRIMINATOR {
+            return Err(anyhow!("Account is not a Switchboard aggregator account"));
+        }
+
+        Ok(Self {
+            result: SwitchboardDecimal::read(data, RESULT_MANTISSA_OFFSET, RESULT_SCALE_OFFSET)?,
+            std_deviation: SwitchboardDecimal::read(
+                data,
+                STD_DEVIATION_MANTISSA_OFFSET,
+                STD_DEVIATION_SCALE_OFFSET,
+            )?,
+            round_open_slot: u64::from_le_bytes(
+                data[ROUND_OPEN_SLOT_OFFSET..ROUND_OPEN_SLOT_OFFSET + 8].try_into()?,
+            ),
+        })
+    }
+}
+
+impl OracleKind {
+    pub fn decode(&self, data: &[u8]) -> Result<OraclePrice> {
+        match self {
+            OracleKind::Pyth => {
+                let price = PythPrice::decode(data)?;
+                Ok(OraclePrice {
+                    price: price.ui_price(),
+                    conf: price.ui_conf(),
+                    publish_slot: price.publish_slot,
+                })
+            }
+            OracleKind::Switchboard => {
+                let aggregator = SwitchboardAggregator::decode(data)?;
+                Ok(OraclePrice {
+                    price: aggregator.result.to_f64(),
+                    conf: aggregator.std_deviation.to_f64(),
+                    publish_slot: aggregator.round_open_slot,
+                })
+            }
+        }
+    }
+}
+
+pub async fn fetch_oracle_price(
+    rpc_client: Arc<dyn Rpc>,
+    kind: OracleKind,
+    account: &Pubkey,
+) -> Result<OraclePrice> {
+    let account = rpc_client
+        .get_account(account)?
+        .ok_or_else(|| anyhow!("Oracle account {} not found", account))?;
+    kind.decode(&account.data)
+}
+
+pub async fn fetch_pyth_price(rpc_client: Arc<dyn Rpc>, price_account: &Pubkey) -> Result<f64> {
+    Ok(fetch_oracle_price(rpc_client, OracleKind::Pyth, price_account).await?.price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pyth_account(price: i64, conf: u64, expo: i32, status: u32) -> Vec<u8> {
+        let mut data = vec![0u8; PYTH_PRICE_MIN_SIZE];
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[8..12].copy_from_slice(&PYTH_PRICE_ACCOUNT_TYPE.to_le_bytes());
+        data[EXPO_OFFSET..EXPO_OFFSET + 4].copy_from_slice(&expo.to_le_bytes());
+        data[AGG_PRICE_OFFSET..AGG_PRICE_OFFSET + 8].copy_from_slice(&price.to_le_bytes());
+        data[AGG_CONF_OFFSET..AGG_CONF_OFFSET + 8].copy_from_slice(&conf.to_le_bytes());
+        data[AGG_STATUS_OFFSET..AGG_STATUS_OFFSET + 4].copy_from_slice(&status.to_le_bytes());
+        data[AGG_PUB_SLOT_OFFSET..AGG_PUB_SLOT_OFFSET + 8].copy_from_slice(&1234u64.to_le_bytes());
+        data
+    }
+
+    fn switchboard_account(result: i128, result_scale: u32, std_dev: i128, std_dev_scale: u32) -> Vec<u8> {
+        let mut data = vec![0u8; SWITCHBOARD_AGGREGATOR_MIN_SIZE];
+        data[0..8].copy_from_slice(&SWITCHBOARD_AGGREGATOR_DISCRIMINATOR);
+        data[ROUND_OPEN_SLOT_OFFSET..ROUND_OPEN_SLOT_OFFSET + 8].copy_from_slice(&99u64.to_le_bytes());
+        data[RESULT_MANTISSA_OFFSET..RESULT_MANTISSA_OFFSET + 16].copy_from_slice(&result.to_le_bytes());
+        data[RESULT_SCALE_OFFSET..RESULT_SCALE_OFFSET + 4].copy_from_slice(&result_scale.to_le_bytes());
+        data[STD_DEVIATION_MANTISSA_OFFSET..STD_DEVIATION_MANTISSA_OFFSET + 16].copy_from_slice(&std_dev.to_le_bytes());
+        data[STD_DEVIATION_SCALE_OFFSET..STD_DEVIATION_SCALE_OFFSET + 4].copy_from_slice(&std_dev_scale.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn decodes_pyth_price_with_exponent() {
+        let price = OracleKind::Pyth.decode(&pyth_account(15_012_345_678, 2_500_000, -8, PYTH_STATUS_TRADING)).unwrap();
+
+        assert!((price.price - 150.12345678).abs() < 1e-9);
+        assert!((price.conf - 0.025).abs() < 1e-12);
+        assert_eq!(price.publish_slot, 1234);
+    }
+
+    #[test]
+    fn rejects_pyth_price_that_is_not_trading() {
+        let error = PythPrice::decode(&pyth_account(100, 1, 0, 0)).unwrap_err();
+        assert!(error.to_string().contains("not trading"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_other_pyth_accounts() {
+        let mut data = pyth_account(100, 1, 0, PYTH_STATUS_TRADING);
+        data[8..12].copy_from_slice(&2u32.to_le_bytes());
+
+        assert!(PythPrice::decode(&data).is_err());
+        assert!(PythPrice::decode(&data[..PYTH_PRICE_MIN_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn decodes_switchboard_round() {
+        let price = OracleKind::Switchboard.decode(&switchboard_account(1_501_234, 4, 25, 3)).unwrap();
+
+        assert!((price.price - 150.1234).abs() < 1e-9);
+        assert!((price.conf - 0.025).abs() < 1e-12);
+        assert_eq!(price.publish_slot, 99);
+    }
+
+    #[test]
+    fn rejects_other_switchboard_accounts() {
+        let mut data = switchboard_account(1, 0, 0, 0);
+        data[0] ^= 0xff;
+
+        assert!(SwitchboardAggregator::decode(&data).is_err());
+        assert!(SwitchboardAggregator::decode(&data[..SWITCHBOARD_AGGREGATOR_MIN_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn parses_oracle_kind() {
+        assert_eq!("Pyth".parse::<OracleKind>().unwrap(), OracleKind::Pyth);
+        assert_eq!("switchboard".parse::<OracleKind>().unwrap(), OracleKind::Switchboard);
+        assert!("chainlink".parse::<OracleKind>().is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use super::rotating::{RotatingFile, RotationPolicy};
+use super::{OutputSink, Rolling};
+use crate::dex::{ArbitrageOpportunity, OracleDeviation, TokenPrice};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+
+/// Flat CSV with a header row at the top of every file, one rotating file
+/// per record type
+pub struct CsvSink {
+    prices: RotatingFile,
+    opportunities: RotatingFile,
+    deviations: RotatingFile,
+}
+
+impl CsvSink {
+    pub fn new(data_dir: &Path, rolling: Rolling, policy: &RotationPolicy) -> Self {
+        let file = |prefix| RotatingFile::new(data_dir, prefix, "csv", policy.clone()).with_rolling(rolling);
+        Self {
+            prices: file("prices"),
+            opportunities: file("arbitrage_opportunities"),
+            deviations: file("oracle_deviations"),
+        }
+    }
+
+    fn write_records<T: Serialize>(file: &mut RotatingFile, records: &[T]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut writer = ::csv::WriterBuilder::new().has_headers(true).from_writer(Vec::new());
+        for record in records {
+            writer.serialize(record)?;
+        }
+        let mut rows = writer.into_inner().context("Failed to serialize CSV rows")?;
+
+        // Field names never contain line breaks, so the header is the first line
+        let header_len = rows.iter().position(|&b| b == b'\n').map(|i| i + 1).unwrap_or(rows.len());
+        let body = rows.split_off(header_len);
+        file.append_with_header(&rows, &body)
+    }
+}
+
+#[async_trait]
+impl OutputSink for CsvSink {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    async fn write_prices(&mut self, prices: &[TokenPrice]) -> Result<()> {
+        Self::write_records(&mut self.prices, prices)
+    }
+
+    async fn write_opportunities(&mut self, opportunities: &[ArbitrageOpportunity]) -> Result<()> {
+        Self::write_records(&mut self.opportunities, opportunities)
+    }
+
+    async fn write_deviations(&mut self, deviations: &[OracleDeviation]) -> Result<()> {
+        Self::write_records(&mut self.deviations, deviations)
+    }
+}
+
+pub fn read_records<T: DeserializeOwned>(file_path: &Path) -> Result<Vec<T>> {
+    let mut reader = ::csv::Reader::from_path(file_path)
+        .with_context(|| format!("Failed to open {}", file_path.display()))?;
+    let records = reader.deserialize().collect::<Result<Vec<T>, _>>()?;
+    Ok(records)
+}
+
+pub fn read_prices(file_path: &Path) -> Result<Vec<TokenPrice>> {
+    read_records(file_path)
+}
+
+pub fn read_opportunities(file_path: &Path) -> Result<Vec<ArbitrageOpportunity>> {
+    read_records(file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("raytx-csv-{}-{}", name, std::process::id()))
+    }
+
+    fn price(price: f64) -> TokenPrice {
+        TokenPrice {
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            dex_name: "Meteora".to_string(),
+            price,
+            quote_mint: "EPjFWdd5AufqSSqeM2qNksxvRUbxz6hmw8FeDh8iw6zQ".to_string(),
+            price_usd: None,
+            liquidity_usd: Some(5_000.0),
+            timestamp: Local::now(),
+        }
+    }
+
+    fn only_file(dir: &Path) -> PathBuf {
+        let files: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        assert_eq!(files.len(), 1, "{:?}", files);
+        files[0].clone()
+    }
+
+    #[tokio::test]
+    async fn daily_file_appends_under_one_header() {
+        let dir = temp_dir("prices");
+        let mut sink = CsvSink::new(&dir, Rolling::Daily, &RotationPolicy::default());
+
+        sink.write_prices(&[price(1.5)]).await.unwrap();
+        sink.write_prices(&[price(0.000_001_234_567_891)]).await.unwrap();
+        let file = only_file(&dir);
+        let contents = fs::read_to_string(&file).unwrap();
+        let read = read_prices(&file).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(contents.matches("token_address").count(), 1);
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].price, 1.5);
+        assert_eq!(read[1].price, 0.000_001_234_567_891);
+        assert_eq!(read[1].price_usd, None);
+        assert_eq!(read[1].liquidity_usd, Some(5_000.0));
+    }
+
+    #[tokio::test]
+    async fn opportunities_round_trip() {
+        let dir = temp_dir("opportunities");
+        let mut sink = CsvSink::new(&dir, Rolling::Daily, &RotationPolicy::default());
+        let written = ArbitrageOpportunity::new(
+            "So11111111111111111111111111111111111111112".to_string(),
+            Some("Wrapped SOL".to_string()),
+            ("Raydium", 0.0001),
+            ("Meteora", 0.000105),
+            "So11111111111111111111111111111111111111112".to_string(),
+        );
+
+        sink.write_opportunities(std::slice::from_ref(&written)).await.unwrap();
+        let read = read_opportunities(&only_file(&dir)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].token_name.as_deref(), Some("Wrapped SOL"));
+        assert_eq!(read[0].price_difference_percent, written.price_difference_percent);
+        assert_eq!(read[0].timestamp, written.timestamp);
+    }
+
+    #[tokio::test]
+    async fn every_rotated_file_starts_with_a_header() {
+        let dir = temp_dir("rotated");
+        let policy = RotationPolicy {
+            max_file_size: Some(1),
+            compress: false,
+            max_age: None,
+            max_total_size: None,
+        };
+        let mut sink = CsvSink::new(&dir, Rolling::Daily, &policy);
+
+        for i in 0..3 {
+            sink.write_prices(&[price(i as f64)]).await.unwrap();
+        }
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        files.sort();
+        let mut read: Vec<f64> = files.iter().flat_map(|file| read_prices(file).unwrap()).map(|p| p.price).collect();
+        read.sort_by(f64::total_cmp);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files.len(), 3, "{:?}", files);
+        assert_eq!(read, vec![0.0, 1.0, 2.0]);
+    }
+
+    #[tokio::test]
+    async fn per_cycle_files_fall_under_retention() {
+        let dir = temp_dir("retention");
+        let policy = RotationPolicy {
+            max_file_size: None,
+            compress: true,
+            max_age: None,
+            max_total_size: Some(0),
+        };
+        let mut sink = CsvSink::new(&dir, Rolling::PerCycle, &policy);
+
+        for i in 0..3 {
+            sink.write_prices(&[price(i as f64)]).await.unwrap();
+        }
+        let file = only_file(&dir);
+        let read = read_prices(&file).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].price, 2.0);
+    }
+}
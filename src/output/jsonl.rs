@@ -0,0 +1,166 @@
+use super::rotating::{RotatingFile, RotationPolicy};
+use super::OutputSink;
+use crate::dex::{ArbitrageOpportunity, OracleDeviation, TokenPrice};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::path::Path;
+
+/// Bumped whenever a field is renamed or removed; adding fields keeps the version.
+/// Version 2 replaced the opportunities' `raydium_price` and `meteora_price`
+/// with `buy_venue`, `buy_price`, `sell_venue` and `sell_price`.
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct Record<'a, T: Serialize> {
+    schema_version: u32,
+    record_type: &'static str,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+/// One JSON object per line, one rotating file per record type
+pub struct JsonlSink {
+    prices: RotatingFile,
+    opportunities: RotatingFile,
+    deviations: RotatingFile,
+}
+
+impl JsonlSink {
+    pub fn new(data_dir: &Path, policy: &RotationPolicy) -> Self {
+        Self {
+            prices: RotatingFile::new(data_dir, "prices", "jsonl", policy.clone()),
+            opportunities: RotatingFile::new(data_dir, "arbitrage_opportunities", "jsonl", policy.clone()),
+            deviations: RotatingFile::new(data_dir, "oracle_deviations", "jsonl", policy.clone()),
+        }
+    }
+
+    fn write_records<T: Serialize>(file: &mut RotatingFile, record_type: &'static str, records: &[T]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut lines = String::new();
+        for data in records {
+            lines.push_str(&serde_json::to_string(&Record {
+                schema_version: SCHEMA_VERSION,
+                record_type,
+                data,
+            })?);
+            lines.push('\n');
+        }
+        file.append(lines.as_bytes())
+    }
+}
+
+#[async_trait]
+impl OutputSink for JsonlSink {
+    fn name(&self) -> &str {
+        "jsonl"
+    }
+
+    async fn write_prices(&mut self, prices: &[TokenPrice]) -> Result<()> {
+        Self::write_records(&mut self.prices, "price", prices)
+    }
+
+    async fn write_opportunities(&mut self, opportunities: &[ArbitrageOpportunity]) -> Result<()> {
+        Self::write_records(&mut self.opportunities, "opportunity", opportunities)
+    }
+
+    async fn write_deviations(&mut self, deviations: &[OracleDeviation]) -> Result<()> {
+        Self::write_records(&mut self.deviations, "oracle_deviation", deviations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+    use serde_json::Value;
+    use std::fs;
+
+    fn price() -> TokenPrice {
+        TokenPrice {
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            dex_name: "Orca".to_string(),
+            price: 1.5,
+            quote_mint: "EPjFWdd5AufqSSqeM2qNksxvRUbxz6hmw8FeDh8iw6zQ".to_string(),
+            price_usd: None,
+            liquidity_usd: Some(2_000.0),
+            timestamp: Local::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_versioned_envelope_per_line() {
+        let dir = std::env::temp_dir().join(format!("raytx-jsonl-{}", std::process::id()));
+        let mut sink = JsonlSink::new(&dir, &RotationPolicy::default());
+
+        sink.write_prices(&[price(), price()]).await.unwrap();
+        sink.write_opportunities(&[]).await.unwrap();
+
+        let files: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        assert_eq!(files.len(), 1, "{:?}", files);
+        let contents = fs::read_to_string(&files[0]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let lines: Vec<Value> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["schema_version"], SCHEMA_VERSION);
+        assert_eq!(lines[0]["record_type"], "price");
+        assert_eq!(lines[0]["dex_name"], "Orca");
+        assert_eq!(lines[0]["price"], 1.5);
+        assert_eq!(lines[0]["price_usd"], Value::Null);
+
+        // The envelope stays readable as the record itself
+        let record: TokenPrice = serde_json::from_value(lines[1].clone()).unwrap();
+        assert_eq!(record.liquidity_usd, Some(2_000.0));
+    }
+
+    // Changing these keys means bumping SCHEMA_VERSION
+    #[tokio::test]
+    async fn opportunity_keys_match_the_schema_version() {
+        let dir = std::env::temp_dir().join(format!("raytx-jsonl-keys-{}", std::process::id()));
+        let mut sink = JsonlSink::new(&dir, &RotationPolicy::default());
+        let opportunity = ArbitrageOpportunity::new(
+            "So11111111111111111111111111111111111111112".to_string(),
+            None,
+            ("Orca", 1.05),
+            ("Raydium", 1.0),
+            "EPjFWdd5AufqSSqeM2qNksxvRUbxz6hmw8FeDh8iw6zQ".to_string(),
+        );
+
+        sink.write_opportunities(&[opportunity]).await.unwrap();
+        let files: Vec<_> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        let contents = fs::read_to_string(&files[0]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let line: Value = serde_json::from_str(contents.trim_end()).unwrap();
+        let mut keys: Vec<&str> = line.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(SCHEMA_VERSION, 2);
+        assert_eq!(
+            keys,
+            [
+                "buy_price",
+                "buy_venue",
+                "liquidity_usd",
+                "net_difference_percent",
+                "price_difference_percent",
+                "price_usd",
+                "profit_usd",
+                "quote_mint",
+                "record_type",
+                "schema_version",
+                "sell_price",
+                "sell_venue",
+                "timestamp",
+                "token_address",
+                "token_name",
+                "transfer_fee_bps",
+            ]
+        );
+        assert_eq!(line["buy_venue"], "Raydium");
+        assert_eq!(line["sell_venue"], "Orca");
+    }
+}
@@ -0,0 +1,126 @@
+use crate::dex::{ArbitrageOpportunity, OracleDeviation, TokenPrice};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub mod csv;
+pub mod jsonl;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod rotating;
+pub mod sqlite;
+pub mod text;
+
+use self::csv::CsvSink;
+use jsonl::JsonlSink;
+use rotating::RotationPolicy;
+use sqlite::SqliteStore;
+use text::TextSink;
+
+#[async_trait]
+pub trait OutputSink: Send + Sync {
+    fn name(&self) -> &str;
+    async fn write_prices(&mut self, prices: &[TokenPrice]) -> Result<()>;
+    async fn write_opportunities(&mut self, opportunities: &[ArbitrageOpportunity]) -> Result<()>;
+    async fn write_deviations(&mut self, deviations: &[OracleDeviation]) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Jsonl,
+    Csv,
+    #[cfg(feature = "parquet")]
+    Parquet,
+    Sqlite,
+    #[cfg(feature = "postgres")]
+    Postgres,
+}
+
+#[derive(Debug, Clone)]
+pub struct SinkOptions {
+    pub data_dir: PathBuf,
+    pub rolling: Rolling,
+    pub postgres_url: Option<String>,
+    pub timescale: bool,
+    pub scanner_id: String,
+    pub rotation: RotationPolicy,
+}
+
+impl OutputFormat {
+    pub fn create_sink(&self, options: &SinkOptions) -> Result<Box<dyn OutputSink>> {
+        let data_dir = options.data_dir.as_path();
+        Ok(match self {
+            OutputFormat::Text => Box::new(TextSink::new(data_dir, &options.rotation)),
+            OutputFormat::Jsonl => Box::new(JsonlSink::new(data_dir, &options.rotation)),
+            OutputFormat::Csv => Box::new(CsvSink::new(data_dir, options.rolling, &options.rotation)),
+            #[cfg(feature = "parquet")]
+            OutputFormat::Parquet => Box::new(parquet::ParquetSink::new(data_dir, options.rolling, &options.rotation)),
+            OutputFormat::Sqlite => Box::new(SqliteStore::open(&data_dir.join(SQLITE_FILE_NAME))?),
+            #[cfg(feature = "postgres")]
+            OutputFormat::Postgres => {
+                let url = options
+                    .postgres_url
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("The postgres output needs a database URL"))?;
+                Box::new(postgres::PostgresSink::new(
+                    postgres::PostgresWriter::new(url, options.timescale)?,
+                    &options.scanner_id,
+                ))
+            }
+        })
+    }
+}
+
+pub const SQLITE_FILE_NAME: &str = "raytx.db";
+
+/// How CSV and parquet files are split: a new file every cycle, or one file
+/// per day. Either way they fall under the rotation policy's retention limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rolling {
+    PerCycle,
+    Daily,
+}
+
+impl Rolling {
+    pub fn file_stem(&self, prefix: &str) -> String {
+        let now = chrono::Local::now();
+        match self {
+            Rolling::PerCycle => format!("{}_{}", prefix, now.format("%Y%m%d_%H%M%S")),
+            Rolling::Daily => format!("{}_{}", prefix, now.format("%Y%m%d")),
+        }
+    }
+}
+
+impl FromStr for Rolling {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "cycle" => Ok(Rolling::PerCycle),
+            "daily" => Ok(Rolling::Daily),
+            _ => Err(anyhow!("Unknown output rolling: {}. Expected cycle or daily", s)),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "jsonl" | "json" => Ok(OutputFormat::Jsonl),
+            "csv" => Ok(OutputFormat::Csv),
+            #[cfg(feature = "parquet")]
+            "parquet" => Ok(OutputFormat::Parquet),
+            "sqlite" => Ok(OutputFormat::Sqlite),
+            #[cfg(feature = "postgres")]
+            "postgres" => Ok(OutputFormat::Postgres),
+            _ => Err(anyhow!("Unknown output format: {}. Expected text, jsonl, csv, parquet, sqlite or postgres", s)),
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use super::rotating::{enforce_retention, unused_path, RotationPolicy};
+use super::{OutputSink, Rolling};
+use crate::dex::{ArbitrageOpportunity, OracleDeviation, TokenPrice};
+use anyhow::{anyhow, Context, Result};
+use arrow::array::{
+    Array, ArrayRef, Decimal128Array, Float64Array, StringArray, TimestampMicrosecondArray, UInt16Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Prices and USD amounts are stored as DECIMAL(38, 18)
+const DECIMAL_PRECISION: u8 = 38;
+const DECIMAL_SCALE: i8 = 18;
+
+fn decimal_type() -> DataType {
+    DataType::Decimal128(DECIMAL_PRECISION, DECIMAL_SCALE)
+}
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+}
+
+fn to_decimal(value: f64) -> i128 {
+    (value * 10f64.powi(DECIMAL_SCALE as i32)).round() as i128
+}
+
+fn from_decimal(value: i128) -> f64 {
+    value as f64 / 10f64.powi(DECIMAL_SCALE as i32)
+}
+
+fn string_array<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(values.collect::<StringArray>())
+}
+
+fn decimal_array(values: impl Iterator<Item = Option<f64>>) -> Result<ArrayRef> {
+    let array = values
+        .map(|value| value.map(to_decimal))
+        .collect::<Decimal128Array>()
+        .with_precision_and_scale(DECIMAL_PRECISION, DECIMAL_SCALE)?;
+    Ok(Arc::new(array))
+}
+
+fn timestamp_array(values: impl Iterator<Item = DateTime<Local>>) -> ArrayRef {
+    let micros: Vec<i64> = values.map(|ts| ts.timestamp_micros()).collect();
+    Arc::new(TimestampMicrosecondArray::from(micros).with_timezone("UTC"))
+}
+
+fn column<'a, A: Array + 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a A> {
+    batch
+        .column_by_name(name)
+        .and_then(|column| column.as_any().downcast_ref::<A>())
+        .ok_or_else(|| anyhow!("Missing or mistyped parquet column: {}", name))
+}
+
+fn optional_string(array: &StringArray, i: usize) -> Option<String> {
+    (!array.is_null(i)).then(|| array.value(i).to_string())
+}
+
+fn optional_decimal(array: &Decimal128Array, i: usize) -> Option<f64> {
+    (!array.is_null(i)).then(|| from_decimal(array.value(i)))
+}
+
+fn timestamp_value(array: &TimestampMicrosecondArray, i: usize) -> Result<DateTime<Local>> {
+    Utc.timestamp_micros(array.value(i))
+        .single()
+        .map(|ts| ts.with_timezone(&Local))
+        .ok_or_else(|| anyhow!("Invalid timestamp in parquet file"))
+}
+
+/// Record types with a fixed columnar layout
+pub trait ParquetRecord: Sized + Clone + Send + Sync {
+    const PREFIX: &'static str;
+
+    fn schema() -> Schema;
+    fn to_batch(records: &[Self]) -> Result<RecordBatch>;
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>>;
+}
+
+impl ParquetRecord for TokenPrice {
+    const PREFIX: &'static str = "prices";
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("token_address", DataType::Utf8, false),
+            Field::new("dex_name", DataType::Utf8, false),
+            Field::new("price", decimal_type(), false),
+            Field::new("quote_mint", DataType::Utf8, false),
+            Field::new("price_usd", decimal_type(), true),
+            Field::new("liquidity_usd", decimal_type(), true),
+            Field::new("timestamp", timestamp_type(), false),
+        ])
+    }
+
+    fn to_batch(records: &[Self]) -> Result<RecordBatch> {
+        let columns = vec![
+            string_array(records.iter().map(|r| Some(r.token_address.as_str()))),
+            string_array(records.iter().map(|r| Some(r.dex_name.as_str()))),
+            decimal_array(records.iter().map(|r| Some(r.price)))?,
+            string_array(records.iter().map(|r| Some(r.quote_mint.as_str()))),
+            decimal_array(records.iter().map(|r| r.price_usd))?,
+            decimal_array(records.iter().map(|r| r.liquidity_usd))?,
+            timestamp_array(records.iter().map(|r| r.timestamp)),
+        ];
+        Ok(RecordBatch::try_new(Arc::new(Self::schema()), columns)?)
+    }
+
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>> {
+        let token_address = column::<StringArray>(batch, "token_address")?;
+        let dex_name = column::<StringArray>(batch, "dex_name")?;
+        let price = column::<Decimal128Array>(batch, "price")?;
+        let quote_mint = column::<StringArray>(batch, "quote_mint")?;
+        let price_usd = column::<Decimal128Array>(batch, "price_usd")?;
+        let liquidity_usd = column::<Decimal128Array>(batch, "liquidity_usd")?;
+        let timestamp = column::<TimestampMicrosecondArray>(batch, "timestamp")?;
+
+        (0..batch.num_rows())
+            .map(|i| {
+                Ok(TokenPrice {
+                    token_address: token_address.value(i).to_string(),
+                    dex_name: dex_name.value(i).to_string(),
+                    price: from_decimal(price.value(i)),
+                    quote_mint: quote_mint.value(i).to_string(),
+                    price_usd: optional_decimal(price_usd, i),
+                    liquidity_usd: optional_decimal(liquidity_usd, i),
+                    timestamp: timestamp_value(timestamp, i)?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl ParquetRecord for ArbitrageOpportunity {
+    const PREFIX: &'static str = "arbitrage_opportunities";
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("token_address", DataType::Utf8, false),
+            Field::new("token_name", DataType::Utf8, true),
+            Field::new("buy_venue", DataType::Utf8, false),
+            Field::new("buy_price", decimal_type(), false),
+            Field::new("sell_venue", DataType::Utf8, false),
+            Field::new("sell_price", decimal_type(), false),
+            Field::new("quote_mint", DataType::Utf8, false),
+            Field::new("price_difference_percent", DataType::Float64, false),
+            Field::new("transfer_fee_bps", DataType::UInt16, false),
+            Field::new("net_difference_percent", DataType::Float64, false),
+            Field::new("price_usd", decimal_type(), true),
+            Field::new("liquidity_usd", decimal_type(), true),
+            Field::new("profit_usd", decimal_type(), true),
+            Field::new("timestamp", timestamp_type(), false),
+        ])
+    }
+
+    fn to_batch(records: &[Self]) -> Result<RecordBatch> {
+        let columns = vec![
+            string_array(records.iter().map(|r| Some(r.token_address.as_str()))),
+            string_array(records.iter().map(|r| r.token_name.as_deref())),
+            string_array(records.iter().map(|r| Some(r.buy_venue.as_str()))),
+            decimal_array(records.iter().map(|r| Some(r.buy_price)))?,
+            string_array(records.iter().map(|r| Some(r.sell_venue.as_str()))),
+            decimal_array(records.iter().map(|r| Some(r.sell_price)))?,
+            string_array(records.iter().map(|r| Some(r.quote_mint.as_str()))),
+            Arc::new(records.iter().map(|r| r.price_difference_percent).collect::<Float64Array>()) as ArrayRef,
+            Arc::new(records.iter().map(|r| r.transfer_fee_bps).collect::<UInt16Array>()) as ArrayRef,
+            Arc::new(records.iter().map(|r| r.net_difference_percent).collect::<Float64Array>()) as ArrayRef,
+            decimal_array(records.iter().map(|r| r.price_usd))?,
+            decimal_array(records.iter().map(|r| r.liquidity_usd))?,
+            decimal_array(records.iter().map(|r| r.profit_usd))?,
+            timestamp_array(records.iter().map(|r| r.timestamp)),
+        ];
+        Ok(RecordBatch::try_new(Arc::new(Self::schema()), columns)?)
+    }
+
+    fn from_batch(batch: &RecordBatch) -> Result<Vec<Self>> {
+        let token_address = column::<StringArray>(batch, "token_address")?;
+        let token_name = column::<StringArray>(batch, "token_name")?;
+        let buy_venue = column::<StringArray>(batch, "buy_venue")?;
+        let buy_price = column::<Decimal128Array>(batch, "buy_price")?;
+        let sell_venue = column::<StringArray>(batch, "sell_venue")?;
+        let sell_price = column::<Decimal128Array>(batch, "sell_price")?;
+        let quote_mint = column::<StringArray>(batch, "quote_mint")?;
+        let price_difference_percent = column::<Float64Array>(batch, "price_difference_percent")?;
+        let transfer_fee_bps = column::<UInt16Array>(batch, "transfer_fee_bps")?;
+        let net_difference_percent = column::<Float64Array>(batch, "net_difference_percent")?;
+        let price_usd = column::<Decimal128Array>(batch, "price_usd")?;
+        let liquidity_usd = column::<Decimal128Array>(batch, "liquidity_usd")?;
+        let profit_usd = column::<Decimal128Array>(batch, "profit_usd")?;
+        let timestamp = column::<TimestampMicrosecondArray>(batch, "timestamp")?;
+
+        (0..batch.num_rows())
+            .map(|i| {
+                Ok(ArbitrageOpportunity {
+                    token_address: token_address.value(i).to_string(),
+                    token_name: optional_string(token_name, i),
+                    buy_venue: buy_venue.value(i).to_string(),
+                    buy_price: from_decimal(buy_price.value(i)),
+                    sell_venue: sell_venue.value(i).to_string(),
+                    sell_price: from_decimal(sell_price.value(i)),
+                    quote_mint: quote_mint.value(i).to_string(),
+                    price_difference_percent: price_difference_percent.value(i),
+                    transfer_fee_bps: transfer_fee_bps.value(i),
+                    net_difference_percent: net_difference_percent.value(i),
+                    price_usd: optional_decimal(price_usd, i),
+                    liquidity_usd: optional_decimal(liquidity_usd, i),
+                    profit_usd: optional_decimal(profit_usd, i),
+                    timestamp: timestamp_value(timestamp, i)?,
+                })
+            })
+            .collect()
+    }
+}
+
+// Parquet files can't be appended to, so every flush writes a file of its
+// own. Daily files of the same day share a stem, e.g. `prices_20261018*.parquet`.
+// They're already compressed, so only the retention limits apply.
+fn write_records<T: ParquetRecord>(
+    data_dir: &Path,
+    rolling: Rolling,
+    policy: &RotationPolicy,
+    records: &[T],
+) -> Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(data_dir).context("Failed to create directory")?;
+    let file_path = unused_path(data_dir, &rolling.file_stem(T::PREFIX), "parquet");
+    write_file(&file_path, records)?;
+    enforce_retention(data_dir, T::PREFIX, "parquet", policy, Some(&file_path))
+}
+
+fn write_file<T: ParquetRecord>(file_path: &Path, records: &[T]) -> Result<()> {
+    let batch = T::to_batch(records)?;
+    let tmp_path = file_path.with_extension("parquet.tmp");
+    let file = fs::File::create(&tmp_path).context("Failed to create parquet file")?;
+
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    fs::rename(&tmp_path, file_path).context("Failed to move parquet file into place")?;
+    Ok(())
+}
+
+pub fn read_records<T: ParquetRecord>(file_path: &Path) -> Result<Vec<T>> {
+    let file = fs::File::open(file_path)
+        .with_context(|| format!("Failed to open {}", file_path.display()))?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+
+    let mut records = Vec::new();
+    for batch in reader {
+        records.extend(T::from_batch(&batch?)?);
+    }
+    Ok(records)
+}
+
+pub fn read_prices(file_path: &Path) -> Result<Vec<TokenPrice>> {
+    read_records(file_path)
+}
+
+pub fn read_opportunities(file_path: &Path) -> Result<Vec<ArbitrageOpportunity>> {
+    read_records(file_path)
+}
+
+/// Columnar export of prices and opportunities for analytics; oracle
+/// deviations stay in the row-oriented sinks
+pub struct ParquetSink {
+    data_dir: PathBuf,
+    rolling: Rolling,
+    policy: RotationPolicy,
+}
+
+impl ParquetSink {
+    pub fn new(data_dir: &Path, rolling: Rolling, policy: &RotationPolicy) -> Self {
+        Self {
+            data_dir: data_dir.to_path_buf(),
+            rolling,
+            policy: policy.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl OutputSink for ParquetSink {
+    fn name(&self) -> &str {
+        "parquet"
+    }
+
+    async fn write_prices(&mut self, prices: &[TokenPrice]) -> Result<()> {
+        write_records(&self.data_dir, self.rolling, &self.policy, prices)
+    }
+
+    async fn write_opportunities(&mut self, opportunities: &[ArbitrageOpportunity]) -> Result<()> {
+        write_records(&self.data_dir, self.rolling, &self.policy, opportunities)
+    }
+
+    async fn write_deviations(&mut self, _deviations: &[OracleDeviation]) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("raytx-parquet-{}-{}", name, std::process::id()))
+    }
+
+    fn price(price: f64, price_usd: Option<f64>) -> TokenPrice {
+        TokenPrice {
+            token_address: "So11111111111111111111111111111111111111112".to_string(),
+            dex_name: "Orca".to_string(),
+            price,
+            quote_mint: "EPjFWdd5AufqSSqeM2qNksxvRUbxz6hmw8FeDh8iw6zQ".to_string(),
+            price_usd,
+            liquidity_usd: None,
+            timestamp: Local::now(),
+        }
+    }
+
+    fn parquet_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn prices_round_trip_at_decimal_precision() {
+        let dir = temp_dir("prices");
+        // Smallest step of DECIMAL(38, 18), a memecoin price and a large one
+        let written = vec![
+            price(1e-18, None),
+            price(0.000_012_345_678_9, Some(0.002_345)),
+            price(123_456_789.123_456_78, Some(1.0)),
+        ];
+
+        write_records(&dir, Rolling::PerCycle, &RotationPolicy::default(), &written).unwrap();
+        let files = parquet_files(&dir);
+        let read = read_prices(&files[0]).unwrap();
+        let schema = SerializedFileReader::new(fs::File::open(&files[0]).unwrap())
+            .unwrap()
+            .metadata()
+            .file_metadata()
+            .schema_descr()
+            .column(2)
+            .logical_type();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(read.len(), written.len());
+        for (read, written) in read.iter().zip(&written) {
+            assert!((read.price - written.price).abs() <= written.price * 1e-15, "{} != {}", read.price, written.price);
+            assert_eq!(read.price_usd, written.price_usd);
+            assert_eq!(read.dex_name, written.dex_name);
+            assert_eq!(read.timestamp.timestamp_micros(), written.timestamp.timestamp_micros());
+        }
+        assert_eq!(
+            schema,
+            Some(parquet::basic::LogicalType::Decimal { scale: 18, precision: 38 })
+        );
+    }
+
+    #[test]
+    fn opportunities_round_trip() {
+        let dir = temp_dir("opportunities");
+        let written = ArbitrageOpportunity::new(
+            "So11111111111111111111111111111111111111112".to_string(),
+            None,
+            ("Meteora", 0.000105),
+            ("Raydium", 0.0001),
+            "So11111111111111111111111111111111111111112".to_string(),
+        );
+
+        write_records(&dir, Rolling::Daily, &RotationPolicy::default(), std::slice::from_ref(&written)).unwrap();
+        let read = read_opportunities(&parquet_files(&dir)[0]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].token_name, None);
+        assert_eq!(read[0].venues(), (("Raydium", 0.0001), ("Meteora", 0.000105)));
+        assert_eq!(read[0].net_difference_percent, written.net_difference_percent);
+    }
+
+    #[test]
+    fn every_flush_writes_its_own_file() {
+        let dir = temp_dir("daily");
+
+        write_records(&dir, Rolling::Daily, &RotationPolicy::default(), &[price(1.0, None)]).unwrap();
+        write_records(&dir, Rolling::Daily, &RotationPolicy::default(), &[price(2.0, None), price(3.0, None)]).unwrap();
+        write_records::<TokenPrice>(&dir, Rolling::Daily, &RotationPolicy::default(), &[]).unwrap();
+        let files = parquet_files(&dir);
+        let counts: Vec<usize> = files.iter().map(|file| read_prices(file).unwrap().len()).collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(counts, [1, 2]);
+    }
+
+    #[test]
+    fn retention_keeps_the_newest_file() {
+        let dir = temp_dir("retention");
+        let policy = RotationPolicy {
+            max_total_size: Some(0),
+            ..RotationPolicy::default()
+        };
+
+        for i in 0..3 {
+            write_records(&dir, Rolling::Daily, &policy, &[price(i as f64, None)]).unwrap();
+        }
+        let files = parquet_files(&dir);
+        let read = read_prices(&files[0]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files.len(), 1, "{:?}", files);
+        assert_eq!(read[0].price, 2.0);
+    }
+}
@@ -0,0 +1,538 @@
+use super::OutputSink;
+use crate::dex::{ArbitrageOpportunity, OracleDeviation, TokenPrice};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_postgres::{Client, Config, NoTls};
+use tracing::{error, info, warn};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait between two attempts while the database is unreachable
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_BUFFERED_BATCHES: usize = 1_000;
+
+// Time column first and no surrogate keys, so both tables can be turned into
+// TimescaleDB hypertables partitioned on `observed_at`
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS price_observations (
+        observed_at TIMESTAMPTZ NOT NULL,
+        scanner TEXT NOT NULL,
+        mint TEXT NOT NULL,
+        dex TEXT NOT NULL,
+        quote_mint TEXT NOT NULL,
+        price DOUBLE PRECISION NOT NULL,
+        price_usd DOUBLE PRECISION,
+        liquidity_usd DOUBLE PRECISION
+    );
+    CREATE INDEX IF NOT EXISTS idx_price_observations_mint_dex_time
+        ON price_observations (mint, dex, observed_at DESC);
+    CREATE TABLE IF NOT EXISTS opportunities (
+        observed_at TIMESTAMPTZ NOT NULL,
+        scanner TEXT NOT NULL,
+        mint TEXT NOT NULL,
+        token_name TEXT,
+        buy_venue TEXT NOT NULL,
+        buy_price DOUBLE PRECISION NOT NULL,
+        sell_venue TEXT NOT NULL,
+        sell_price DOUBLE PRECISION NOT NULL,
+        quote_mint TEXT NOT NULL,
+        price_difference_percent DOUBLE PRECISION NOT NULL,
+        transfer_fee_bps INTEGER NOT NULL,
+        net_difference_percent DOUBLE PRECISION NOT NULL,
+        price_usd DOUBLE PRECISION,
+        liquidity_usd DOUBLE PRECISION,
+        profit_usd DOUBLE PRECISION
+    );
+    -- Tables from before venue pairs hold Raydium and Meteora columns; the
+    -- cheaper one becomes the buy side
+    DO $$
+    BEGIN
+        IF EXISTS (
+            SELECT 1 FROM information_schema.columns
+            WHERE table_schema = current_schema() AND table_name = 'opportunities' AND column_name = 'raydium_price'
+        ) THEN
+            ALTER TABLE opportunities
+                ADD COLUMN buy_venue TEXT,
+                ADD COLUMN buy_price DOUBLE PRECISION,
+                ADD COLUMN sell_venue TEXT,
+                ADD COLUMN sell_price DOUBLE PRECISION;
+            UPDATE opportunities SET
+                buy_venue = CASE WHEN raydium_price <= meteora_price THEN 'Raydium' ELSE 'Meteora' END,
+                buy_price = LEAST(raydium_price, meteora_price),
+                sell_venue = CASE WHEN raydium_price <= meteora_price THEN 'Meteora' ELSE 'Raydium' END,
+                sell_price = GREATEST(raydium_price, meteora_price);
+            ALTER TABLE opportunities
+                ALTER COLUMN buy_venue SET NOT NULL,
+                ALTER COLUMN buy_price SET NOT NULL,
+                ALTER COLUMN sell_venue SET NOT NULL,
+                ALTER COLUMN sell_price SET NOT NULL,
+                DROP COLUMN raydium_price,
+                DROP COLUMN meteora_price;
+        END IF;
+    END $$;
+    CREATE INDEX IF NOT EXISTS idx_opportunities_mint_time
+        ON opportunities (mint, observed_at DESC);
+";
+
+const HYPERTABLES: &str = "
+    SELECT create_hypertable('price_observations', 'observed_at', if_not_exists => TRUE);
+    SELECT create_hypertable('opportunities', 'observed_at', if_not_exists => TRUE);
+";
+
+#[derive(Debug, Clone)]
+pub enum Batch {
+    Prices(Vec<TokenPrice>),
+    Opportunities(Vec<ArbitrageOpportunity>),
+}
+
+impl Batch {
+    fn len(&self) -> usize {
+        match self {
+            Batch::Prices(prices) => prices.len(),
+            Batch::Opportunities(opportunities) => opportunities.len(),
+        }
+    }
+}
+
+/// Destination of buffered batches, implemented by the real database client
+/// and by an in-process fake
+#[async_trait]
+pub trait BatchWriter: Send + Sync {
+    async fn write_batch(&mut self, scanner: &str, batch: &Batch) -> Result<()>;
+}
+
+pub struct PostgresWriter {
+    config: Config,
+    timescale: bool,
+    client: Option<Client>,
+}
+
+impl PostgresWriter {
+    /// A `connect_timeout` in the URL replaces the 5 second default
+    pub fn new(url: &str, timescale: bool) -> Result<Self> {
+        let mut config: Config = url.parse().context("Invalid Postgres URL")?;
+        if config.get_connect_timeout().is_none() {
+            config.connect_timeout(CONNECT_TIMEOUT);
+        }
+
+        Ok(Self {
+            config,
+            timescale,
+            client: None,
+        })
+    }
+
+    // Connects lazily and again after the connection drops
+    async fn client(&mut self) -> Result<&Client> {
+        if self.client.as_ref().map(|client| client.is_closed()).unwrap_or(true) {
+            let (client, connection) = self
+                .config
+                .connect(NoTls)
+                .await
+                .context("Failed to connect to Postgres")?;
+            tokio::spawn(async move {
+                if let Err(e) = connection.await {
+                    error!(error = %e, "Postgres connection error");
+                }
+            });
+
+            client.batch_execute(SCHEMA).await.context("Failed to create Postgres schema")?;
+            if self.timescale {
+                client
+                    .batch_execute(HYPERTABLES)
+                    .await
+                    .context("Failed to create TimescaleDB hypertables")?;
+            }
+            self.client = Some(client);
+        }
+
+        self.client.as_ref().ok_or_else(|| anyhow!("Postgres client unavailable"))
+    }
+}
+
+#[async_trait]
+impl BatchWriter for PostgresWriter {
+    async fn write_batch(&mut self, scanner: &str, batch: &Batch) -> Result<()> {
+        let client = self.client().await?;
+
+        // One statement per batch: columns are sent as arrays and unnested
+        // server-side instead of building a multi-row VALUES list
+        match batch {
+            Batch::Prices(prices) => {
+                let observed_at: Vec<DateTime<Utc>> = prices.iter().map(|p| p.timestamp.with_timezone(&Utc)).collect();
+                let scanners: Vec<&str> = vec![scanner; prices.len()];
+                let mints: Vec<&str> = prices.iter().map(|p| p.token_address.as_str()).collect();
+                let dexes: Vec<&str> = prices.iter().map(|p| p.dex_name.as_str()).collect();
+                let quote_mints: Vec<&str> = prices.iter().map(|p| p.quote_mint.as_str()).collect();
+                let price_values: Vec<f64> = prices.iter().map(|p| p.price).collect();
+                let prices_usd: Vec<Option<f64>> = prices.iter().map(|p| p.price_usd).collect();
+                let liquidity_usd: Vec<Option<f64>> = prices.iter().map(|p| p.liquidity_usd).collect();
+
+                client
+                    .execute(
+                        "INSERT INTO price_observations
+                            (observed_at, scanner, mint, dex, quote_mint, price, price_usd, liquidity_usd)
+                         SELECT * FROM UNNEST(
+                            $1::timestamptz[], $2::text[], $3::text[], $4::text[], $5::text[],
+                            $6::float8[], $7::float8[], $8::float8[]
+                         )",
+                        &[&observed_at, &scanners, &mints, &dexes, &quote_mints, &price_values, &prices_usd, &liquidity_usd],
+                    )
+                    .await?;
+            }
+            Batch::Opportunities(opportunities) => {
+                let observed_at: Vec<DateTime<Utc>> =
+                    opportunities.iter().map(|o| o.timestamp.with_timezone(&Utc)).collect();
+                let scanners: Vec<&str> = vec![scanner; opportunities.len()];
+                let mints: Vec<&str> = opportunities.iter().map(|o| o.token_address.as_str()).collect();
+                let token_names: Vec<Option<&str>> = opportunities.iter().map(|o| o.token_name.as_deref()).collect();
+                let buy_venues: Vec<&str> = opportunities.iter().map(|o| o.buy_venue.as_str()).collect();
+                let buy_prices: Vec<f64> = opportunities.iter().map(|o| o.buy_price).collect();
+                let sell_venues: Vec<&str> = opportunities.iter().map(|o| o.sell_venue.as_str()).collect();
+                let sell_prices: Vec<f64> = opportunities.iter().map(|o| o.sell_price).collect();
+                let quote_mints: Vec<&str> = opportunities.iter().map(|o| o.quote_mint.as_str()).collect();
+                let differences: Vec<f64> = opportunities.iter().map(|o| o.price_difference_percent).collect();
+                let transfer_fees: Vec<i32> = opportunities.iter().map(|o| o.transfer_fee_bps as i32).collect();
+                let net_differences: Vec<f64> = opportunities.iter().map(|o| o.net_difference_percent).collect();
+                let prices_usd: Vec<Option<f64>> = opportunities.iter().map(|o| o.price_usd).collect();
+                let liquidity_usd: Vec<Option<f64>> = opportunities.iter().map(|o| o.liquidity_usd).collect();
+                let profit_usd: Vec<Option<f64>> = opportunities.iter().map(|o| o.profit_usd).collect();
+
+                client
+                    .execute(
+                        "INSERT INTO opportunities
+                            (observed_at, scanner, mint, token_name, buy_venue, buy_price, sell_venue, sell_price,
+                             quote_mint, price_difference_percent, transfer_fee_bps, net_difference_percent,
+                             price_usd, liquidity_usd, profit_usd)
+                         SELECT * FROM UNNEST(
+                            $1::timestamptz[], $2::text[], $3::text[], $4::text[], $5::text[], $6::float8[],
+                            $7::text[], $8::float8[], $9::text[], $10::float8[], $11::int4[], $12::float8[],
+                            $13::float8[], $14::float8[], $15::float8[]
+                         )",
+                        &[
+                            &observed_at, &scanners, &mints, &token_names, &buy_venues, &buy_prices,
+                            &sell_venues, &sell_prices, &quote_mints, &differences, &transfer_fees, &net_differences, &prices_usd,
+                            &liquidity_usd, &profit_usd,
+                        ],
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// In-process stand-in for Postgres that keeps written batches in memory and
+/// can be switched offline to exercise buffering
+#[derive(Default)]
+pub struct MemoryWriter {
+    batches: Mutex<Vec<(String, Batch)>>,
+    offline: AtomicBool,
+}
+
+impl MemoryWriter {
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::SeqCst);
+    }
+
+    pub fn batches(&self) -> Vec<(String, Batch)> {
+        self.batches.lock().map(|batches| batches.clone()).unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl BatchWriter for Arc<MemoryWriter> {
+    async fn write_batch(&mut self, scanner: &str, batch: &Batch) -> Result<()> {
+        if self.offline.load(Ordering::SeqCst) {
+            return Err(anyhow!("MemoryWriter is offline"));
+        }
+
+        self.batches
+            .lock()
+            .map_err(|_| anyhow!("MemoryWriter lock poisoned"))?
+            .push((scanner.to_string(), batch.clone()));
+        Ok(())
+    }
+}
+
+// Batches waiting for the database, numbered so the writer task can tell
+// whether the batch it just wrote was dropped for space meanwhile
+struct Queue {
+    batches: VecDeque<(u64, Batch)>,
+    next_id: u64,
+    max_batches: usize,
+    closed: bool,
+}
+
+/// Hands batches to a background task that writes them in order, so the
+/// watch cycle never waits on the database. While it's unreachable batches
+/// are buffered and retried with backoff; the oldest are dropped past the
+/// buffer limit.
+pub struct PostgresSink {
+    queue: Arc<Mutex<Queue>>,
+    wake: Arc<Notify>,
+}
+
+impl PostgresSink {
+    pub fn new<W: BatchWriter + 'static>(writer: W, scanner: &str) -> Self {
+        Self::with_limits(writer, scanner, DEFAULT_MAX_BUFFERED_BATCHES, RETRY_DELAY)
+    }
+
+    /// `retry_delay` doubles after every failed attempt, up to [`MAX_RETRY_DELAY`]
+    pub fn with_limits<W: BatchWriter + 'static>(
+        writer: W,
+        scanner: &str,
+        max_buffered_batches: usize,
+        retry_delay: Duration,
+    ) -> Self {
+        let queue = Arc::new(Mutex::new(Queue {
+            batches: VecDeque::new(),
+            next_id: 0,
+            max_batches: max_buffered_batches,
+            closed: false,
+        }));
+        let wake = Arc::new(Notify::new());
+
+        tokio::spawn(write_pending(writer, scanner.to_string(), queue.clone(), wake.clone(), retry_delay));
+        Self { queue, wake }
+    }
+
+    /// Batches buffered or being written
+    pub fn pending_batches(&self) -> usize {
+        self.queue.lock().map(|queue| queue.batches.len()).unwrap_or_default()
+    }
+
+    fn enqueue(&self, batch: Batch) -> Result<()> {
+        if batch.len() == 0 {
+            return Ok(());
+        }
+
+        let mut queue = self.queue.lock().map_err(|_| anyhow!("Postgres queue lock poisoned"))?;
+        let id = queue.next_id;
+        queue.next_id += 1;
+        queue.batches.push_back((id, batch));
+        while queue.batches.len() > queue.max_batches {
+            if let Some((_, dropped)) = queue.batches.pop_front() {
+                warn!(rows = dropped.len(), "Postgres buffer full, dropping buffered rows");
+            }
+        }
+        drop(queue);
+
+        self.wake.notify_one();
+        Ok(())
+    }
+}
+
+// Whatever is still buffered gets written before the task stops, or dropped
+// if the database is down
+impl Drop for PostgresSink {
+    fn drop(&mut self) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.closed = true;
+        }
+        self.wake.notify_one();
+    }
+}
+
+async fn write_pending<W: BatchWriter>(
+    mut writer: W,
+    scanner: String,
+    queue: Arc<Mutex<Queue>>,
+    wake: Arc<Notify>,
+    retry_delay: Duration,
+) {
+    let mut delay = retry_delay;
+    let mut failing = false;
+
+    loop {
+        let next = match queue.lock() {
+            Ok(queue) => match queue.batches.front() {
+                Some((id, batch)) => Some((*id, batch.clone(), queue.closed)),
+                None if queue.closed => return,
+                None => None,
+            },
+            Err(_) => return,
+        };
+        let (id, batch, closed) = match next {
+            Some(next) => next,
+            None => {
+                wake.notified().await;
+                continue;
+            }
+        };
+
+        let result = match tokio::time::timeout(WRITE_TIMEOUT, writer.write_batch(&scanner, &batch)).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow!("timed out after {:?}", WRITE_TIMEOUT)),
+        };
+
+        match result {
+            Ok(()) => {
+                if let Ok(mut queue) = queue.lock() {
+                    if queue.batches.front().map(|(front, _)| *front) == Some(id) {
+                        queue.batches.pop_front();
+                    }
+                }
+                if failing {
+                    info!("Postgres writes resumed");
+                }
+                failing = false;
+                delay = retry_delay;
+            }
+            Err(e) if closed => {
+                let rows: usize = queue
+                    .lock()
+                    .map(|queue| queue.batches.iter().map(|(_, batch)| batch.len()).sum())
+                    .unwrap_or_default();
+                error!(rows, error = %e, "Postgres sink closed, dropping buffered rows");
+                return;
+            }
+            Err(e) => {
+                let pending = queue.lock().map(|queue| queue.batches.len()).unwrap_or_default();
+                if !failing {
+                    warn!(pending_batches = pending, error = %format_args!("{:#}", e), "Postgres write failed, buffering");
+                }
+                failing = true;
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl OutputSink for PostgresSink {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    async fn write_prices(&mut self, prices: &[TokenPrice]) -> Result<()> {
+        self.enqueue(Batch::Prices(prices.to_vec()))
+    }
+
+    async fn write_opportunities(&mut self, opportunities: &[ArbitrageOpportunity]) -> Result<()> {
+        self.enqueue(Batch::Opportunities(opportunities.to_vec()))
+    }
+
+    // The shared history covers prices and opportunities only
+    async fn write_deviations(&mut self, _deviations: &[OracleDeviation]) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    const RETRY: Duration = Duration::from_millis(10);
+
+    fn prices(count: usize) -> Vec<TokenPrice> {
+        (0..count)
+            .map(|i| TokenPrice {
+                token_address: format!("Mint{}", i),
+                dex_name: "Raydium".to_string(),
+                price: 0.5 + i as f64,
+                quote_mint: "So11111111111111111111111111111111111111112".to_string(),
+                price_usd: None,
+                liquidity_usd: None,
+                timestamp: Local::now(),
+            })
+            .collect()
+    }
+
+    fn written_rows(writer: &MemoryWriter) -> Vec<usize> {
+        writer.batches().iter().map(|(_, batch)| batch.len()).collect()
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        for _ in 0..500 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition not reached in time");
+    }
+
+    #[tokio::test]
+    async fn writes_return_while_offline_and_flush_in_order_once_online() {
+        let writer = Arc::new(MemoryWriter::default());
+        writer.set_offline(true);
+        let mut sink = PostgresSink::with_limits(writer.clone(), "scanner-a", 10, RETRY);
+
+        sink.write_prices(&prices(1)).await.unwrap();
+        sink.write_prices(&prices(2)).await.unwrap();
+        sink.write_prices(&prices(3)).await.unwrap();
+        assert_eq!(sink.pending_batches(), 3);
+        tokio::time::sleep(RETRY * 3).await;
+        assert!(writer.batches().is_empty());
+        assert_eq!(sink.pending_batches(), 3);
+
+        writer.set_offline(false);
+        wait_until(|| sink.pending_batches() == 0).await;
+        assert_eq!(written_rows(&writer), vec![1, 2, 3]);
+        assert!(writer.batches().iter().all(|(scanner, _)| scanner == "scanner-a"));
+    }
+
+    #[tokio::test]
+    async fn drops_oldest_batches_past_the_limit() {
+        let writer = Arc::new(MemoryWriter::default());
+        writer.set_offline(true);
+        let mut sink = PostgresSink::with_limits(writer.clone(), "scanner-a", 2, RETRY);
+
+        for count in 1..=4 {
+            sink.write_prices(&prices(count)).await.unwrap();
+        }
+        assert_eq!(sink.pending_batches(), 2);
+
+        writer.set_offline(false);
+        wait_until(|| sink.pending_batches() == 0).await;
+        assert_eq!(written_rows(&writer), vec![3, 4]);
+    }
+
+    #[tokio::test]
+    async fn skips_empty_batches() {
+        let writer = Arc::new(MemoryWriter::default());
+        let mut sink = PostgresSink::with_limits(writer.clone(), "scanner-a", 2, RETRY);
+
+        sink.write_prices(&[]).await.unwrap();
+        sink.write_opportunities(&[]).await.unwrap();
+        sink.write_prices(&prices(2)).await.unwrap();
+        wait_until(|| sink.pending_batches() == 0).await;
+        assert_eq!(written_rows(&writer), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn drains_buffer_after_the_sink_is_dropped() {
+        let writer = Arc::new(MemoryWriter::default());
+        writer.set_offline(true);
+        let mut sink = PostgresSink::with_limits(writer.clone(), "scanner-a", 10, RETRY);
+        sink.write_prices(&prices(1)).await.unwrap();
+        sink.write_prices(&prices(2)).await.unwrap();
+
+        writer.set_offline(false);
+        drop(sink);
+        wait_until(|| writer.batches().len() == 2).await;
+        assert_eq!(written_rows(&writer), vec![1, 2]);
+    }
+
+    #[test]
+    fn rejects_invalid_urls_and_keeps_explicit_connect_timeout() {
+        assert!(PostgresWriter::new("not a url", false).is_err());
+
+        let writer = PostgresWriter::new("postgres://user@localhost/raytx", false).unwrap();
+        assert_eq!(writer.config.get_connect_timeout(), Some(&CONNECT_TIMEOUT));
+
+        let writer = PostgresWriter::new("postgres://user@localhost/raytx?connect_timeout=2", true).unwrap();
+        assert_eq!(writer.config.get_connect_timeout(), Some(&Duration::from_secs(2)));
+    }
+}
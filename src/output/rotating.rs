@@ -0,0 +1,313 @@
+use super::Rolling;
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+#[derive(Debug, Clone)]
+pub struct RotationPolicy {
+    /// Start a new segment once the active file would grow past this size
+    pub max_file_size: Option<u64>,
+    /// Gzip closed segments
+    pub compress: bool,
+    /// Delete closed segments older than this
+    pub max_age: Option<Duration>,
+    /// Delete the oldest closed segments while their total size exceeds this
+    pub max_total_size: Option<u64>,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_file_size: Some(64 * 1024 * 1024),
+            compress: true,
+            max_age: Some(Duration::from_secs(30 * 24 * 60 * 60)),
+            max_total_size: Some(1024 * 1024 * 1024),
+        }
+    }
+}
+
+/// `<stem>.<extension>` in `dir`, or `<stem>_<n>.<extension>` with the first
+/// `n` that isn't taken by a plain or gzipped file
+pub fn unused_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let taken = |path: &Path| {
+        let mut gz_name = path.as_os_str().to_owned();
+        gz_name.push(".gz");
+        path.exists() || Path::new(&gz_name).exists()
+    };
+
+    let mut path = dir.join(format!("{}.{}", stem, extension));
+    let mut n = 0;
+    while taken(&path) {
+        n += 1;
+        path = dir.join(format!("{}_{}.{}", stem, n, extension));
+    }
+    path
+}
+
+/// Append-only file named `<prefix>_<YYYYMMDD>.<extension>`, rotated at day
+/// boundaries and when it outgrows the size limit. Rolling per cycle starts a
+/// new file on every append instead.
+pub struct RotatingFile {
+    dir: PathBuf,
+    prefix: String,
+    extension: String,
+    policy: RotationPolicy,
+    rolling: Rolling,
+    active_path: Option<PathBuf>,
+}
+
+impl RotatingFile {
+    pub fn new(dir: &Path, prefix: &str, extension: &str, policy: RotationPolicy) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            prefix: prefix.to_string(),
+            extension: extension.to_string(),
+            policy,
+            rolling: Rolling::Daily,
+            active_path: None,
+        }
+    }
+
+    pub fn with_rolling(mut self, rolling: Rolling) -> Self {
+        self.rolling = rolling;
+        self
+    }
+
+    fn path_for_today(&self) -> PathBuf {
+        let day = chrono::Local::now().format("%Y%m%d");
+        self.dir.join(format!("{}_{}.{}", self.prefix, day, self.extension))
+    }
+
+    pub fn append(&mut self, bytes: &[u8]) -> Result<()> {
+        self.append_with_header(&[], bytes)
+    }
+
+    /// Appends `bytes`, preceded by `header` when the write starts a new file
+    pub fn append_with_header(&mut self, header: &[u8], bytes: &[u8]) -> Result<()> {
+        let path = self.prepare(bytes.len())?;
+        let is_new = fs::metadata(&path).map(|m| m.len() == 0).unwrap_or(true);
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        if is_new {
+            file.write_all(header)?;
+        }
+        file.write_all(bytes)?;
+        Ok(())
+    }
+
+    fn prepare(&mut self, incoming: usize) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir).context("Failed to create directory")?;
+        let today = match self.rolling {
+            Rolling::Daily => self.path_for_today(),
+            Rolling::PerCycle => unused_path(&self.dir, &self.rolling.file_stem(&self.prefix), &self.extension),
+        };
+
+        if self.active_path.as_ref() != Some(&today) {
+            // First write, a new day or a new cycle: close every earlier segment
+            self.active_path = Some(today.clone());
+            self.close_stale_segments(&today)?;
+        } else if let Some(max_file_size) = self.policy.max_file_size {
+            let size = fs::metadata(&today).map(|m| m.len()).unwrap_or(0);
+            if size > 0 && size + incoming as u64 > max_file_size {
+                self.rotate(&today)?;
+            }
+        }
+
+        Ok(today)
+    }
+
+    // Several rotations within one second get `_1`, `_2`, ... suffixes
+    // instead of overwriting each other
+    fn rotate(&self, active: &Path) -> Result<()> {
+        let stem = format!("{}_{}", self.prefix, chrono::Local::now().format("%Y%m%d_%H%M%S"));
+        let rotated = unused_path(&self.dir, &stem, &self.extension);
+        fs::rename(active, &rotated).context("Failed to rotate file")?;
+        self.close_segment(&rotated)?;
+        self.enforce_retention()
+    }
+
+    fn close_stale_segments(&self, active: &Path) -> Result<()> {
+        for segment in segments(&self.dir, &self.prefix, &self.extension)? {
+            if segment != active && !is_compressed(&segment) {
+                self.close_segment(&segment)?;
+            }
+        }
+        self.enforce_retention()
+    }
+
+    fn close_segment(&self, segment: &Path) -> Result<()> {
+        if !self.policy.compress {
+            return Ok(());
+        }
+
+        let mut gz_name = segment.as_os_str().to_owned();
+        gz_name.push(".gz");
+        let gz_path = PathBuf::from(gz_name);
+
+        let mut input = fs::File::open(segment)?;
+        let output = fs::File::create(&gz_path)?;
+        let mut encoder = GzEncoder::new(output, Compression::default());
+        io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?;
+        fs::remove_file(segment).context("Failed to remove compressed segment")?;
+        Ok(())
+    }
+
+    fn enforce_retention(&self) -> Result<()> {
+        enforce_retention(&self.dir, &self.prefix, &self.extension, &self.policy, self.active_path.as_deref())
+    }
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.extension().map(|ext| ext == "gz").unwrap_or(false)
+}
+
+// Every `<prefix>_*.<extension>` file in `dir`, plain or gzipped
+fn segments(dir: &Path, prefix: &str, extension: &str) -> Result<Vec<PathBuf>> {
+    let name_prefix = format!("{}_", prefix);
+    let plain_suffix = format!(".{}", extension);
+    let gz_suffix = format!(".{}.gz", extension);
+
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+        if name.starts_with(&name_prefix) && (name.ends_with(&plain_suffix) || name.ends_with(&gz_suffix)) {
+            segments.push(path);
+        }
+    }
+    Ok(segments)
+}
+
+/// Applies the age and total size limits of `policy` to the
+/// `<prefix>_*.<extension>` files in `dir`, never touching `active`
+pub fn enforce_retention(
+    dir: &Path,
+    prefix: &str,
+    extension: &str,
+    policy: &RotationPolicy,
+    active: Option<&Path>,
+) -> Result<()> {
+    let now = SystemTime::now();
+    let mut closed = Vec::new();
+
+    for segment in segments(dir, prefix, extension)? {
+        if Some(segment.as_path()) == active {
+            continue;
+        }
+        let metadata = fs::metadata(&segment)?;
+        let modified = metadata.modified().unwrap_or(now);
+        closed.push((segment, modified, metadata.len()));
+    }
+
+    // Oldest first
+    closed.sort_by_key(|(_, modified, _)| *modified);
+
+    if let Some(max_age) = policy.max_age {
+        closed.retain(|(segment, modified, _)| {
+            let expired = now.duration_since(*modified).map(|age| age > max_age).unwrap_or(false);
+            if expired {
+                if let Err(e) = fs::remove_file(segment) {
+                    warn!(path = %segment.display(), error = %e, "Failed to remove expired file");
+                }
+            }
+            !expired
+        });
+    }
+
+    if let Some(max_total_size) = policy.max_total_size {
+        let mut total: u64 = closed.iter().map(|(_, _, size)| size).sum();
+        for (segment, _, size) in &closed {
+            if total <= max_total_size {
+                break;
+            }
+            fs::remove_file(segment)
+                .with_context(|| format!("Failed to remove {}", segment.display()))?;
+            total -= size;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("raytx-rotating-{}-{}", name, std::process::id()))
+    }
+
+    fn files(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn unused_path_skips_plain_and_gzipped_files() {
+        let dir = temp_dir("unused");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("prices_1.log"), "").unwrap();
+        assert_eq!(unused_path(&dir, "prices_1", "log"), dir.join("prices_1_1.log"));
+        fs::write(dir.join("prices_1_1.log.gz"), "").unwrap();
+        assert_eq!(unused_path(&dir, "prices_1", "log"), dir.join("prices_1_2.log"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotations_within_one_second_keep_every_segment() {
+        let dir = temp_dir("same-second");
+        let policy = RotationPolicy {
+            max_file_size: Some(4),
+            compress: false,
+            max_age: None,
+            max_total_size: None,
+        };
+        let mut file = RotatingFile::new(&dir, "prices", "log", policy);
+
+        for line in ["a\n", "b\n", "c\n", "d\n", "e\n"] {
+            file.append(line.as_bytes()).unwrap();
+        }
+        let mut contents: Vec<String> = files(&dir)
+            .iter()
+            .map(|name| fs::read_to_string(dir.join(name)).unwrap())
+            .collect();
+        contents.sort();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(contents.concat(), "a\nb\nc\nd\ne\n");
+    }
+
+    #[test]
+    fn per_cycle_rolling_compresses_earlier_files() {
+        let dir = temp_dir("per-cycle");
+        let mut file = RotatingFile::new(&dir, "prices", "csv", RotationPolicy::default()).with_rolling(Rolling::PerCycle);
+
+        file.append_with_header(b"h\n", b"1\n").unwrap();
+        file.append_with_header(b"h\n", b"2\n").unwrap();
+        let names = files(&dir);
+        let active = names.iter().find(|name| name.ends_with(".csv")).unwrap();
+        let active_contents = fs::read_to_string(dir.join(active)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(names.len(), 2, "{:?}", names);
+        assert_eq!(names.iter().filter(|name| name.ends_with(".csv.gz")).count(), 1);
+        assert_eq!(active_contents, "h\n2\n");
+    }
+}
@@ -0,0 +1,549 @@
+use super::OutputSink;
+use crate::dex::{ArbitrageOpportunity, OracleDeviation, TokenPrice};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Local, TimeZone};
+use rusqlite::{params, Connection, Row};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+// Applied in order; the index of the last applied migration is kept in
+// `PRAGMA user_version`. Never edit a released migration, append a new one.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE tokens (
+        mint TEXT PRIMARY KEY,
+        name TEXT,
+        first_seen_at INTEGER NOT NULL
+    );
+    CREATE TABLE pools (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        dex TEXT NOT NULL,
+        mint TEXT NOT NULL REFERENCES tokens(mint),
+        quote_mint TEXT NOT NULL,
+        UNIQUE (dex, mint, quote_mint)
+    );
+    CREATE INDEX idx_pools_mint_dex ON pools (mint, dex);
+    CREATE TABLE price_observations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        pool_id INTEGER NOT NULL REFERENCES pools(id),
+        price REAL NOT NULL,
+        price_usd REAL,
+        liquidity_usd REAL,
+        observed_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_price_observations_pool_time ON price_observations (pool_id, observed_at);
+    CREATE TABLE opportunities (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        mint TEXT NOT NULL REFERENCES tokens(mint),
+        raydium_price REAL NOT NULL,
+        meteora_price REAL NOT NULL,
+        quote_mint TEXT NOT NULL,
+        price_difference_percent REAL NOT NULL,
+        transfer_fee_bps INTEGER NOT NULL,
+        net_difference_percent REAL NOT NULL,
+        price_usd REAL,
+        liquidity_usd REAL,
+        profit_usd REAL,
+        observed_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_opportunities_mint_time ON opportunities (mint, observed_at);
+    CREATE INDEX idx_opportunities_time ON opportunities (observed_at);",
+    "CREATE TABLE oracle_deviations (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        mint TEXT NOT NULL REFERENCES tokens(mint),
+        dex TEXT NOT NULL,
+        dex_price REAL NOT NULL,
+        oracle_price REAL NOT NULL,
+        oracle_confidence REAL NOT NULL,
+        oracle_stale INTEGER NOT NULL,
+        quote_mint TEXT NOT NULL,
+        deviation_percent REAL NOT NULL,
+        observed_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_oracle_deviations_mint_dex_time ON oracle_deviations (mint, dex, observed_at);",
+    // Opportunities between any two venues: the Raydium and Meteora columns
+    // become the buy side (the cheaper one) and the sell side
+    "CREATE TABLE opportunities_by_venue (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        mint TEXT NOT NULL REFERENCES tokens(mint),
+        buy_venue TEXT NOT NULL,
+        buy_price REAL NOT NULL,
+        sell_venue TEXT NOT NULL,
+        sell_price REAL NOT NULL,
+        quote_mint TEXT NOT NULL,
+        price_difference_percent REAL NOT NULL,
+        transfer_fee_bps INTEGER NOT NULL,
+        net_difference_percent REAL NOT NULL,
+        price_usd REAL,
+        liquidity_usd REAL,
+        profit_usd REAL,
+        observed_at INTEGER NOT NULL
+    );
+    INSERT INTO opportunities_by_venue (
+        id, mint, buy_venue, buy_price, sell_venue, sell_price, quote_mint, price_difference_percent,
+        transfer_fee_bps, net_difference_percent, price_usd, liquidity_usd, profit_usd, observed_at
+    )
+    SELECT
+        id, mint,
+        CASE WHEN raydium_price <= meteora_price THEN 'Raydium' ELSE 'Meteora' END,
+        MIN(raydium_price, meteora_price),
+        CASE WHEN raydium_price <= meteora_price THEN 'Meteora' ELSE 'Raydium' END,
+        MAX(raydium_price, meteora_price),
+        quote_mint, price_difference_percent, transfer_fee_bps, net_difference_percent,
+        price_usd, liquidity_usd, profit_usd, observed_at
+    FROM opportunities;
+    DROP TABLE opportunities;
+    ALTER TABLE opportunities_by_venue RENAME TO opportunities;
+    CREATE INDEX idx_opportunities_mint_time ON opportunities (mint, observed_at);
+    CREATE INDEX idx_opportunities_time ON opportunities (observed_at);",
+];
+
+fn to_millis(timestamp: &DateTime<Local>) -> i64 {
+    timestamp.timestamp_millis()
+}
+
+fn from_millis(millis: i64) -> rusqlite::Result<DateTime<Local>> {
+    Local.timestamp_millis_opt(millis).single().ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            0,
+            rusqlite::types::Type::Integer,
+            format!("Invalid timestamp: {}", millis).into(),
+        )
+    })
+}
+
+/// Price history and opportunities in a single embedded database file
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create directory")?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open SQLite database {}", path.display()))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::with_connection(conn)
+    }
+
+    /// A private database that lives as long as the store, for tests and
+    /// one-off analysis
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        let store = Self { conn: Mutex::new(conn) };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|_| anyhow!("SQLite connection lock poisoned"))
+    }
+
+    fn migrate(&self) -> Result<()> {
+        let mut conn = self.conn()?;
+        let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        if version > MIGRATIONS.len() {
+            return Err(anyhow!(
+                "Database schema version {} is newer than this build supports ({})",
+                version,
+                MIGRATIONS.len()
+            ));
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)
+                .with_context(|| format!("Failed to apply SQLite migration {}", index + 1))?;
+            tx.pragma_update(None, "user_version", index + 1)?;
+            tx.commit()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn insert_prices(&self, prices: &[TokenPrice]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut insert_token = tx.prepare_cached(
+                "INSERT OR IGNORE INTO tokens (mint, first_seen_at) VALUES (?1, ?2)",
+            )?;
+            let mut insert_pool = tx.prepare_cached(
+                "INSERT OR IGNORE INTO pools (dex, mint, quote_mint) VALUES (?1, ?2, ?3)",
+            )?;
+            let mut select_pool = tx.prepare_cached(
+                "SELECT id FROM pools WHERE dex = ?1 AND mint = ?2 AND quote_mint = ?3",
+            )?;
+            let mut insert_price = tx.prepare_cached(
+                "INSERT INTO price_observations (pool_id, price, price_usd, liquidity_usd, observed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+
+            for price in prices {
+                let observed_at = to_millis(&price.timestamp);
+                insert_token.execute(params![price.token_address, observed_at])?;
+                insert_pool.execute(params![price.dex_name, price.token_address, price.quote_mint])?;
+                let pool_id: i64 = select_pool.query_row(
+                    params![price.dex_name, price.token_address, price.quote_mint],
+                    |row| row.get(0),
+                )?;
+                insert_price.execute(params![
+                    pool_id,
+                    price.price,
+                    price.price_usd,
+                    price.liquidity_usd,
+                    observed_at
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn insert_opportunities(&self, opportunities: &[ArbitrageOpportunity]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut upsert_token = tx.prepare_cached(
+                "INSERT INTO tokens (mint, name, first_seen_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (mint) DO UPDATE SET name = COALESCE(excluded.name, tokens.name)",
+            )?;
+            let mut insert_opportunity = tx.prepare_cached(
+                "INSERT INTO opportunities (
+                    mint, buy_venue, buy_price, sell_venue, sell_price, quote_mint,
+                    price_difference_percent, transfer_fee_bps, net_difference_percent, price_usd,
+                    liquidity_usd, profit_usd, observed_at
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+
+            for opp in opportunities {
+                let observed_at = to_millis(&opp.timestamp);
+                upsert_token.execute(params![opp.token_address, opp.token_name, observed_at])?;
+                insert_opportunity.execute(params![
+                    opp.token_address,
+                    opp.buy_venue,
+                    opp.buy_price,
+                    opp.sell_venue,
+                    opp.sell_price,
+                    opp.quote_mint,
+                    opp.price_difference_percent,
+                    opp.transfer_fee_bps,
+                    opp.net_difference_percent,
+                    opp.price_usd,
+                    opp.liquidity_usd,
+                    opp.profit_usd,
+                    observed_at
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn insert_deviations(&self, deviations: &[OracleDeviation]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut insert_token = tx.prepare_cached(
+                "INSERT OR IGNORE INTO tokens (mint, first_seen_at) VALUES (?1, ?2)",
+            )?;
+            let mut insert_deviation = tx.prepare_cached(
+                "INSERT INTO oracle_deviations (
+                    mint, dex, dex_price, oracle_price, oracle_confidence, oracle_stale,
+                    quote_mint, deviation_percent, observed_at
+                 ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+
+            for deviation in deviations {
+                let observed_at = to_millis(&deviation.timestamp);
+                insert_token.execute(params![deviation.token_address, observed_at])?;
+                insert_deviation.execute(params![
+                    deviation.token_address,
+                    deviation.dex_name,
+                    deviation.dex_price,
+                    deviation.oracle_price,
+                    deviation.oracle_confidence,
+                    deviation.oracle_stale,
+                    deviation.quote_mint,
+                    deviation.deviation_percent,
+                    observed_at
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn price_from_row(row: &Row) -> rusqlite::Result<TokenPrice> {
+        Ok(TokenPrice {
+            token_address: row.get("mint")?,
+            dex_name: row.get("dex")?,
+            price: row.get("price")?,
+            quote_mint: row.get("quote_mint")?,
+            price_usd: row.get("price_usd")?,
+            liquidity_usd: row.get("liquidity_usd")?,
+            timestamp: from_millis(row.get("observed_at")?)?,
+        })
+    }
+
+    fn opportunity_from_row(row: &Row) -> rusqlite::Result<ArbitrageOpportunity> {
+        Ok(ArbitrageOpportunity {
+            token_address: row.get("mint")?,
+            token_name: row.get("name")?,
+            buy_venue: row.get("buy_venue")?,
+            buy_price: row.get("buy_price")?,
+            sell_venue: row.get("sell_venue")?,
+            sell_price: row.get("sell_price")?,
+            quote_mint: row.get("quote_mint")?,
+            price_difference_percent: row.get("price_difference_percent")?,
+            transfer_fee_bps: row.get("transfer_fee_bps")?,
+            net_difference_percent: row.get("net_difference_percent")?,
+            price_usd: row.get("price_usd")?,
+            liquidity_usd: row.get("liquidity_usd")?,
+            profit_usd: row.get("profit_usd")?,
+            timestamp: from_millis(row.get("observed_at")?)?,
+        })
+    }
+
+    /// Most recent first
+    pub fn last_prices(&self, mint: &str, dex: &str, limit: usize) -> Result<Vec<TokenPrice>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT p.mint, p.dex, p.quote_mint, o.price, o.price_usd, o.liquidity_usd, o.observed_at
+             FROM price_observations o
+             JOIN pools p ON p.id = o.pool_id
+             WHERE p.mint = ?1 AND p.dex = ?2
+             ORDER BY o.observed_at DESC
+             LIMIT ?3",
+        )?;
+        let prices = stmt
+            .query_map(params![mint, dex, limit as i64], Self::price_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(prices)
+    }
+
+    pub fn prices_between(
+        &self,
+        mint: &str,
+        from: &DateTime<Local>,
+        to: &DateTime<Local>,
+    ) -> Result<Vec<TokenPrice>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT p.mint, p.dex, p.quote_mint, o.price, o.price_usd, o.liquidity_usd, o.observed_at
+             FROM price_observations o
+             JOIN pools p ON p.id = o.pool_id
+             WHERE p.mint = ?1 AND o.observed_at BETWEEN ?2 AND ?3
+             ORDER BY o.observed_at",
+        )?;
+        let prices = stmt
+            .query_map(params![mint, to_millis(from), to_millis(to)], Self::price_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(prices)
+    }
+
+    /// Most recent first, optionally limited to one token
+    pub fn recent_opportunities(&self, mint: Option<&str>, limit: usize) -> Result<Vec<ArbitrageOpportunity>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT o.*, t.name
+             FROM opportunities o
+             JOIN tokens t ON t.mint = o.mint
+             WHERE ?1 IS NULL OR o.mint = ?1
+             ORDER BY o.observed_at DESC
+             LIMIT ?2",
+        )?;
+        let opportunities = stmt
+            .query_map(params![mint, limit as i64], Self::opportunity_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(opportunities)
+    }
+}
+
+#[async_trait]
+impl OutputSink for SqliteStore {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    async fn write_prices(&mut self, prices: &[TokenPrice]) -> Result<()> {
+        self.insert_prices(prices)
+    }
+
+    async fn write_opportunities(&mut self, opportunities: &[ArbitrageOpportunity]) -> Result<()> {
+        self.insert_opportunities(opportunities)
+    }
+
+    async fn write_deviations(&mut self, deviations: &[OracleDeviation]) -> Result<()> {
+        self.insert_deviations(deviations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const MINT: &str = "So11111111111111111111111111111111111111112";
+    const OTHER_MINT: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+    const USDC: &str = "EPjFWdd5AufqSSqeM2qNksxvRUbxz6hmw8FeDh8iw6zQ";
+
+    // Millisecond precision, like the stored timestamps
+    fn minutes_ago(minutes: i64) -> DateTime<Local> {
+        from_millis(to_millis(&(Local::now() - Duration::minutes(minutes)))).unwrap()
+    }
+
+    fn price(mint: &str, dex: &str, price: f64, timestamp: DateTime<Local>) -> TokenPrice {
+        TokenPrice {
+            token_address: mint.to_string(),
+            dex_name: dex.to_string(),
+            price,
+            quote_mint: USDC.to_string(),
+            price_usd: Some(price),
+            liquidity_usd: None,
+            timestamp,
+        }
+    }
+
+    fn user_version(store: &SqliteStore) -> usize {
+        store.conn().unwrap().pragma_query_value(None, "user_version", |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn last_prices_are_newest_first_for_one_venue() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let written = [
+            price(MINT, "Orca", 150.0, minutes_ago(3)),
+            price(MINT, "Orca", 151.0, minutes_ago(2)),
+            price(MINT, "Meteora", 149.0, minutes_ago(2)),
+            price(MINT, "Orca", 152.0, minutes_ago(1)),
+            price(OTHER_MINT, "Orca", 0.00002, minutes_ago(1)),
+        ];
+        store.insert_prices(&written).unwrap();
+
+        let prices = store.last_prices(MINT, "Orca", 2).unwrap();
+
+        let values: Vec<f64> = prices.iter().map(|price| price.price).collect();
+        assert_eq!(values, [152.0, 151.0]);
+        assert_eq!(prices[0].quote_mint, USDC);
+        assert_eq!(prices[0].price_usd, Some(152.0));
+        assert_eq!(prices[0].timestamp, written[3].timestamp);
+    }
+
+    #[test]
+    fn prices_between_covers_every_venue_in_order() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let written = [
+            price(MINT, "Orca", 150.0, minutes_ago(30)),
+            price(MINT, "Meteora", 149.0, minutes_ago(10)),
+            price(MINT, "Orca", 151.0, minutes_ago(5)),
+        ];
+        store.insert_prices(&written).unwrap();
+
+        let prices = store.prices_between(MINT, &minutes_ago(15), &Local::now()).unwrap();
+
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0].dex_name, "Meteora");
+        assert_eq!(prices[0].timestamp, written[1].timestamp);
+        assert_eq!(prices[1].dex_name, "Orca");
+    }
+
+    #[test]
+    fn opportunities_round_trip_with_token_name() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let mut named = ArbitrageOpportunity::new(MINT.to_string(), Some("Wrapped SOL".to_string()), ("Raydium", 1.0), ("Meteora", 1.05), USDC.to_string());
+        named.timestamp = minutes_ago(2);
+        named.profit_usd = Some(12.5);
+        let mut other = ArbitrageOpportunity::new(OTHER_MINT.to_string(), None, ("Orca", 2.1), ("Meteora", 2.0), USDC.to_string());
+        other.timestamp = minutes_ago(1);
+        store.insert_opportunities(&[named.clone(), other]).unwrap();
+
+        let all = store.recent_opportunities(None, 10).unwrap();
+        let one = store.recent_opportunities(Some(MINT), 10).unwrap();
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].token_address, OTHER_MINT);
+        assert_eq!(all[0].venues(), (("Meteora", 2.0), ("Orca", 2.1)));
+        assert_eq!(one.len(), 1);
+        assert_eq!(one[0].token_name.as_deref(), Some("Wrapped SOL"));
+        assert_eq!(one[0].price_difference_percent, named.price_difference_percent);
+        assert_eq!(one[0].profit_usd, Some(12.5));
+        assert_eq!(one[0].timestamp, named.timestamp);
+    }
+
+    #[test]
+    fn fresh_database_gets_every_migration() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        assert_eq!(user_version(&store), MIGRATIONS.len());
+    }
+
+    #[test]
+    fn migrates_from_an_older_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        conn.execute("INSERT INTO tokens (mint, first_seen_at) VALUES (?1, 0)", params![MINT]).unwrap();
+
+        let store = SqliteStore::with_connection(conn).unwrap();
+
+        assert_eq!(user_version(&store), MIGRATIONS.len());
+        let token_count: i64 = store.conn().unwrap().query_row("SELECT COUNT(*) FROM tokens", [], |row| row.get(0)).unwrap();
+        assert_eq!(token_count, 1);
+        store
+            .insert_deviations(&[OracleDeviation {
+                token_address: MINT.to_string(),
+                dex_name: "Orca".to_string(),
+                dex_price: 151.0,
+                oracle_price: 150.0,
+                oracle_confidence: 0.1,
+                oracle_stale: false,
+                quote_mint: USDC.to_string(),
+                deviation_percent: 0.67,
+                timestamp: Local::now(),
+            }])
+            .unwrap();
+    }
+
+    #[test]
+    fn migrates_raydium_meteora_opportunities_to_venues() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch(MIGRATIONS[1]).unwrap();
+        conn.pragma_update(None, "user_version", 2).unwrap();
+        conn.execute("INSERT INTO tokens (mint, first_seen_at) VALUES (?1, 0)", params![MINT]).unwrap();
+        conn.execute(
+            "INSERT INTO opportunities (
+                mint, raydium_price, meteora_price, quote_mint, price_difference_percent,
+                transfer_fee_bps, net_difference_percent, observed_at
+             ) VALUES (?1, 1.05, 1.0, ?2, 5.0, 0, 5.0, 0)",
+            params![MINT, USDC],
+        )
+        .unwrap();
+
+        let store = SqliteStore::with_connection(conn).unwrap();
+        let opportunities = store.recent_opportunities(Some(MINT), 10).unwrap();
+
+        assert_eq!(opportunities.len(), 1);
+        assert_eq!(opportunities[0].venues(), (("Meteora", 1.0), ("Raydium", 1.05)));
+        assert_eq!(opportunities[0].net_difference_percent, 5.0);
+    }
+
+    #[test]
+    fn rejects_a_newer_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() + 1).unwrap();
+
+        let error = SqliteStore::with_connection(conn).err().unwrap();
+        assert!(error.to_string().contains("newer than this build"), "{}", error);
+    }
+}
@@ -0,0 +1,52 @@
+use super::rotating::{RotatingFile, RotationPolicy};
+use super::OutputSink;
+use crate::dex::{ArbitrageOpportunity, OracleDeviation, TokenPrice};
+use crate::price_fetcher::PriceFetcher;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::Path;
+
+/// Human-readable log lines, one rotating file per record type
+pub struct TextSink {
+    prices: RotatingFile,
+    opportunities: RotatingFile,
+    deviations: RotatingFile,
+}
+
+impl TextSink {
+    pub fn new(data_dir: &Path, policy: &RotationPolicy) -> Self {
+        Self {
+            prices: RotatingFile::new(data_dir, "prices", "log", policy.clone()),
+            opportunities: RotatingFile::new(data_dir, "arbitrage_opportunities", "log", policy.clone()),
+            deviations: RotatingFile::new(data_dir, "oracle_deviations", "log", policy.clone()),
+        }
+    }
+
+    fn write_entries<T>(file: &mut RotatingFile, records: &[T], format: fn(&T) -> String) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let entries: String = records.iter().map(format).collect();
+        file.append(entries.as_bytes())
+    }
+}
+
+#[async_trait]
+impl OutputSink for TextSink {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    async fn write_prices(&mut self, prices: &[TokenPrice]) -> Result<()> {
+        Self::write_entries(&mut self.prices, prices, PriceFetcher::format_price)
+    }
+
+    async fn write_opportunities(&mut self, opportunities: &[ArbitrageOpportunity]) -> Result<()> {
+        Self::write_entries(&mut self.opportunities, opportunities, PriceFetcher::format_arbitrage_opportunity)
+    }
+
+    async fn write_deviations(&mut self, deviations: &[OracleDeviation]) -> Result<()> {
+        Self::write_entries(&mut self.deviations, deviations, PriceFetcher::format_oracle_deviation)
+    }
+}
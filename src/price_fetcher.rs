@@ -1,210 +1,558 @@
-use crate::dex::{DexType, TokenPrice, ArbitrageOpportunity};
-use anyhow::{Context, Result};
+use crate::logging;
+use crate::dex::oracle::OracleDex;
+use crate::dex::{lookup, DexError, DexProtocol, LookupErrors, PoolInfo, PoolPrice, TokenPrice, ArbitrageOpportunity, OracleDeviation};
+use crate::mints::{self, TransferFee};
+use crate::quote::{self, QuoteAsset};
+use crate::rpc::Rpc;
+use anyhow::{anyhow, Context, Result};
 use serde_json::Value;
-use solana_client::rpc_client::RpcClient;
 use std::sync::Arc;
-use std::path::Path;
+use std::str::FromStr;
 use tokio::task;
 use futures::future::join_all;
-use std::fs;
-use std::io::Write;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::collections::HashMap;
+use tracing::{debug, debug_span, error, warn, Instrument};
+use solana_sdk::pubkey::Pubkey;
 
+pub const JUPITER_TOKEN_LIST: &str = "https://tokens.jup.ag/tokens?tags=birdeye-trending";
+
+/// Everything one [`PriceFetcher::scan`] observed
+pub struct ScanResult {
+    pub prices: Vec<TokenPrice>,
+    pub opportunities: Vec<ArbitrageOpportunity>,
+    pub errors: LookupErrors,
+    pub sol_usd: Option<f64>,
+}
+
+/// Runs the configured venues over a token list. Defaults to SOL quotes,
+/// no USD view and the Jupiter trending list.
 pub struct PriceFetcher {
-    dexes: Vec<DexType>,
-    rpc_client: Arc<RpcClient>,
+    // Tradable venues only; arbitrage pairs are drawn from these
+    dexes: Vec<Arc<dyn DexProtocol>>,
+    // Priced alongside the venues for tokens it has a feed for, never traded
+    oracle: Option<Arc<OracleDex>>,
+    rpc_client: Arc<dyn Rpc>,
+    quote_asset: QuoteAsset,
+    usd_view: bool,
+    usd_reference_pool: Option<Pubkey>,
+    usd_fallback_oracle: Option<Pubkey>,
+    trade_size_usd: f64,
+    token_list_url: String,
+    watchlist: Vec<String>,
 }
 
 impl PriceFetcher {
-    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+    pub fn new(rpc_client: Arc<dyn Rpc>) -> Self {
         Self {
             dexes: Vec::new(),
+            oracle: None,
             rpc_client,
+            quote_asset: QuoteAsset::Sol,
+            usd_view: false,
+            usd_reference_pool: None,
+            usd_fallback_oracle: None,
+            trade_size_usd: 1000.0,
+            token_list_url: JUPITER_TOKEN_LIST.to_string(),
+            watchlist: Vec::new(),
         }
     }
 
-    pub fn add_dex(&mut self, dex: DexType) {
+    pub fn add_dex(&mut self, dex: Arc<dyn DexProtocol>) {
         self.dexes.push(dex);
     }
 
-    pub async fn fetch_tokens() -> Result<Value> {
-        let client = reqwest::Client::new();
-        let response = client
-            .get("https://tokens.jup.ag/tokens?tags=birdeye-trending")
-            .header("User-Agent", "Mozilla/5.0")
-            .send()
-            .await
-            .context("Failed to send request to Jupiter API")?;
+    /// Sets the oracle pseudo-venue. Its prices are reported for the mints it
+    /// has feeds for and feed [`Self::find_oracle_deviations`], but it's never
+    /// one side of an arbitrage opportunity.
+    pub fn set_oracle(&mut self, oracle: OracleDex) {
+        self.oracle = Some(Arc::new(oracle));
+    }
+
+    // The oracle, when it has a feed for the token
+    fn oracle_for(&self, token_address: &str) -> Option<Arc<dyn DexProtocol>> {
+        let oracle = self.oracle.as_ref()?;
+        let has_feed = oracle.feeds().iter().any(|feed| feed.mint.to_string() == token_address);
+        has_feed.then(|| oracle.clone() as Arc<dyn DexProtocol>)
+    }
+
+    // Every venue that prices the token: the tradable ones, then the oracle
+    fn venues_for(&self, token_address: &str) -> Vec<Arc<dyn DexProtocol>> {
+        self.dexes.iter().cloned().chain(self.oracle_for(token_address)).collect()
+    }
+
+    pub fn set_quote_asset(&mut self, quote_asset: QuoteAsset) {
+        self.quote_asset = quote_asset;
+    }
+
+    pub fn set_usd_view(
+        &mut self,
+        enabled: bool,
+        reference_pool: Option<Pubkey>,
+        fallback_oracle: Option<Pubkey>,
+        trade_size_usd: f64,
+    ) {
+        self.usd_view = enabled;
+        self.usd_reference_pool = reference_pool;
+        self.usd_fallback_oracle = fallback_oracle;
+        self.trade_size_usd = trade_size_usd;
+    }
+
+    /// An empty token list URL scans the watchlist only
+    pub fn set_token_sources(&mut self, token_list_url: &str, watchlist: Vec<String>) {
+        self.token_list_url = token_list_url.to_string();
+        self.watchlist = watchlist;
+    }
+
+    // SOL/USD reference read once per cycle so every venue converts with the same rate
+    async fn fetch_reference_price(&self) -> Option<f64> {
+        let reference_pool = self.usd_reference_pool.as_ref();
+        let fallback_oracle = self.usd_fallback_oracle.as_ref();
+        match quote::fetch_sol_usd_price_with_fallback(self.rpc_client.clone(), reference_pool, fallback_oracle).await {
+            Ok(sol_usd) => Some(sol_usd),
+            Err(e) => {
+                warn!(error = %e, "Failed to fetch SOL/USD reference price");
+                None
+            }
+        }
+    }
+
+    fn fetch_transfer_fee(&self, token_address: &str, epoch: u64) -> Option<TransferFee> {
+        let mint = Pubkey::from_str(token_address).ok()?;
+        match mints::fetch_mint_info(self.rpc_client.as_ref(), &mint, epoch) {
+            Ok(mint_info) => mint_info.transfer_fee,
+            Err(e) => {
+                warn!(mint = token_address, error = %e, "Failed to read mint");
+                None
+            }
+        }
+    }
+
+    fn to_token_price(
+        &self,
+        token_address: String,
+        dex_name: String,
+        pool_price: &PoolPrice,
+        sol_usd: Option<f64>,
+    ) -> Option<TokenPrice> {
+        let price = self.quote_asset.normalize(pool_price, sol_usd)?;
+        let (price_usd, liquidity_usd) = if self.usd_view {
+            (
+                QuoteAsset::Usd.normalize(pool_price, sol_usd),
+                QuoteAsset::Usd.normalize_liquidity(pool_price, sol_usd),
+            )
+        } else {
+            (None, None)
+        };
+
+        Some(TokenPrice {
+            token_address,
+            dex_name,
+            price,
+            quote_mint: self.quote_asset.mint().to_string(),
+            price_usd,
+            liquidity_usd,
+            timestamp: chrono::Local::now(),
+        })
+    }
+
+    /// Token list entries plus the watchlist, as `{"address", "name", ...}` objects
+    pub async fn fetch_tokens(&self) -> Result<Vec<Value>> {
+        let mut tokens = Vec::new();
+
+        if !self.token_list_url.is_empty() {
+            let client = reqwest::Client::new();
+            let response = client
+                .get(&self.token_list_url)
+                .header("User-Agent", "Mozilla/5.0")
+                .send()
+                .await
+                .context("Failed to send request to token list")?;
 
-        if !response.status().is_success() {
-            anyhow::bail!("Failed to fetch tokens: {}", response.status());
+            if !response.status().is_success() {
+                anyhow::bail!("Failed to fetch tokens: {}", response.status());
+            }
+
+            let list: Value = response.json().await.context("Failed to parse JSON response")?;
+            tokens = list.as_array().context("Expected tokens array")?.clone();
+        }
+
+        // Watchlist mints carry no metadata, only the address
+        for mint in &self.watchlist {
+            if !tokens.iter().any(|token| token["address"].as_str() == Some(mint.as_str())) {
+                tokens.push(serde_json::json!({ "address": mint }));
+            }
         }
 
-        let tokens: Value = response.json().await.context("Failed to parse JSON response")?;
         Ok(tokens)
     }
 
     pub async fn fetch_all_prices(&self) -> Result<Vec<TokenPrice>> {
-        let tokens = Self::fetch_tokens().await?;
-        let tokens_array = tokens.as_array().context("Expected tokens array")?;
+        let tokens_array = self.fetch_tokens().await?;
+        let sol_usd = self.fetch_reference_price().await;
 
-        let pb = ProgressBar::new((tokens_array.len() * self.dexes.len()) as u64);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
-            .expect("Failed to set progress bar style"));
+        let venues: Vec<(&str, Vec<Arc<dyn DexProtocol>>)> = tokens_array
+            .iter()
+            .filter_map(|token| token["address"].as_str())
+            .map(|address| (address, self.venues_for(address)))
+            .collect();
+        let pb = logging::progress_bar(venues.iter().map(|(_, dexes)| dexes.len() as u64).sum());
 
         let mut all_tasks = Vec::new();
 
-        for token in tokens_array {
-            if let Some(address) = token["address"].as_str() {
-                let dex_tasks: Vec<_> = self.dexes.iter().map(|dex| {
-                    let rpc_client = self.rpc_client.clone();
-                    let dex_name = dex.name().to_string();
-                    let address = address.to_string();
-                    let pb = pb.clone();
-                    let dex = dex.clone();
-
-                    task::spawn(async move {
-                        let result = dex.get_token_price(rpc_client, &address).await;
-                        pb.inc(1);
-                        (address, dex_name, result)
-                    })
-                }).collect();
-
-                all_tasks.extend(dex_tasks);
-            }
+        for (address, dexes) in &venues {
+            let dex_tasks: Vec<_> = dexes.iter().map(|dex| {
+                let rpc_client = self.rpc_client.clone();
+                let dex_name = dex.name().to_string();
+                let address = address.to_string();
+                let pb = pb.clone();
+                let dex = dex.clone();
+
+                task::spawn(async move {
+                    let result = lookup(dex.as_ref(), rpc_client, &address).await;
+                    pb.inc(1);
+                    (address, dex_name, result)
+                })
+            }).collect();
+
+            all_tasks.extend(dex_tasks);
         }
 
         let results = join_all(all_tasks).await;
         let mut prices = Vec::new();
+        let mut errors = LookupErrors::default();
 
         for result in results {
-            if let Ok((address, dex_name, Ok(Some(price)))) = result {
-                prices.push(TokenPrice {
-                    token_address: address,
-                    dex_name,
-                    price,
-                    timestamp: chrono::Local::now(),
-                });
+            match result {
+                Ok((address, dex_name, Ok(pool_price))) => {
+                    if let Some(price) = self.to_token_price(address, dex_name, &pool_price, sol_usd) {
+                        prices.push(price);
+                    }
+                }
+                Ok((_, dex_name, Err(e))) => errors.record(&dex_name, &e),
+                Err(e) => error!(error = %e, "Price lookup task failed"),
             }
         }
 
         pb.finish_with_message("Completed price fetching");
+        errors.log();
         Ok(prices)
     }
 
-    pub async fn save_price_data(prices: &[TokenPrice], file_path: &Path) -> Result<()> {
-        if let Some(parent) = file_path.parent() {
-            fs::create_dir_all(parent).context("Failed to create directory")?;
+    /// Current price of one token on every venue that lists it
+    pub async fn fetch_token_prices(&self, token_address: &str) -> Result<Vec<TokenPrice>> {
+        Pubkey::from_str(token_address).context("Invalid token mint")?;
+        let sol_usd = self.fetch_reference_price().await;
+        let mut prices = Vec::new();
+
+        for dex in self.venues_for(token_address) {
+            match lookup(dex.as_ref(), self.rpc_client.clone(), token_address).await {
+                Ok(pool_price) => {
+                    if let Some(price) = self.to_token_price(token_address.to_string(), dex.name().to_string(), &pool_price, sol_usd) {
+                        prices.push(price);
+                    }
+                }
+                Err(DexError::PoolNotFound { .. }) => {}
+                Err(e) => warn!(mint = token_address, dex = dex.name(), error = %e, "Failed to fetch price"),
+            }
         }
-    
-        let mut file = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(file_path)
-            .context("Failed to open price log file")?;
-    
-        for price in prices {
-            let log_entry = format!(
-                "[{}] {} on {}: {} SOL\n",
-                price.timestamp.format("%Y-%m-%d %H:%M:%S"),
-                price.token_address,
-                price.dex_name,
-                price.price
-            );
-            file.write_all(log_entry.as_bytes())?;
-            println!("{}", log_entry.trim());
+
+        Ok(prices)
+    }
+
+    /// Decodes a pool account with whichever venue owns it
+    pub async fn decode_pool(&self, address: &Pubkey) -> Result<PoolInfo> {
+        let account = self
+            .rpc_client
+            .get_account(address)
+            .context("Failed to fetch pool account")?
+            .ok_or_else(|| anyhow!("Pool account {} not found", address))?;
+
+        for dex in &self.dexes {
+            if let Some(pool) = dex.decode_pool(self.rpc_client.clone(), address, &account).await? {
+                return Ok(pool);
+            }
+        }
+
+        Err(anyhow!("{} is not a supported pool account (owner {})", address, account.owner))
+    }
+
+    pub fn format_pool(pool: &PoolInfo) -> String {
+        let base_symbol = mints::find_by_address(&pool.base_mint).map(|mint| mint.symbol).unwrap_or("?");
+        let quote_symbol = mints::find_by_address(&pool.quote_mint).map(|mint| mint.symbol).unwrap_or("?");
+        let log_entry = format!(
+            "{} pool {}\n\tBase: {} ({})\n\tQuote: {} ({})\n\tPrice: {} {} per {}\n",
+            pool.dex_name,
+            pool.address,
+            pool.base_mint,
+            base_symbol,
+            pool.quote_mint,
+            quote_symbol,
+            pool.price,
+            quote_symbol,
+            base_symbol
+        );
+        match pool.liquidity {
+            Some(liquidity) => format!("{}\tLiquidity: {:.2} {}\n", log_entry, liquidity, quote_symbol),
+            None => log_entry,
+        }
+    }
+
+    pub fn format_price(price: &TokenPrice) -> String {
+        let log_entry = format!(
+            "[{}] {} on {}: {} {}\n",
+            price.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            price.token_address,
+            price.dex_name,
+            price.price,
+            Self::quote_symbol(&price.quote_mint)
+        );
+        match (price.price_usd, price.liquidity_usd) {
+            (Some(price_usd), Some(liquidity_usd)) => format!(
+                "{} (${} USD, liquidity ${:.2})\n", log_entry.trim_end(), price_usd, liquidity_usd
+            ),
+            (Some(price_usd), None) => format!("{} (${} USD)\n", log_entry.trim_end(), price_usd),
+            _ => log_entry,
         }
-    
-        Ok(())
     }
 
     pub async fn find_arbitrage_opportunities(&self, min_difference: f64) -> Result<Vec<ArbitrageOpportunity>> {
-        let tokens = Self::fetch_tokens().await?;
-        let tokens_array = tokens.as_array().context("Expected tokens array")?;
+        Ok(self.scan(min_difference).await?.opportunities)
+    }
+
+    /// One arbitrage pass, keeping every price observed along the way
+    pub async fn scan(&self, min_difference: f64) -> Result<ScanResult> {
+        let tokens_array = self.fetch_tokens().await?;
+        let sol_usd = self.fetch_reference_price().await;
 
-        let pb = ProgressBar::new(tokens_array.len() as u64);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
-            .expect("Failed to set progress bar style"));
+        let pb = logging::progress_bar(tokens_array.len() as u64);
 
         let mut opportunities = Vec::new();
-        let mut price_map: HashMap<String, HashMap<String, f64>> = HashMap::new();
+        let mut all_prices = Vec::new();
+        // Token, name and its price on each venue, in registration order
+        let mut listed: Vec<(String, Option<String>, Vec<TokenPrice>)> = Vec::new();
+        let mut errors = LookupErrors::default();
 
-        for token in tokens_array {
+        for token in &tokens_array {
             if let Some(address) = token["address"].as_str() {
                 let token_name = token["name"].as_str().map(String::from);
-                let mut token_prices = HashMap::new();
+                let mut token_prices = Vec::new();
 
-                for dex in &self.dexes {
-                    if let Ok(Some(price)) = dex.get_token_price(self.rpc_client.clone(), address).await {
-                        token_prices.insert(dex.name().to_string(), price);
+                async {
+                    for dex in &self.dexes {
+                        match lookup(dex.as_ref(), self.rpc_client.clone(), address).await {
+                            Ok(pool_price) => {
+                                if let Some(price) = self.to_token_price(address.to_string(), dex.name().to_string(), &pool_price, sol_usd) {
+                                    all_prices.push(price.clone());
+                                    token_prices.push(price);
+                                }
+                            }
+                            Err(e) => {
+                                debug!(dex = dex.name(), error = %e, "Price lookup failed");
+                                errors.record(dex.name(), &e);
+                            }
+                        }
+                    }
+                }
+                .instrument(debug_span!("token", mint = address))
+                .await;
+
+                // Reported with the other prices but left out of the pairs
+                if let Some(oracle) = self.oracle_for(address) {
+                    match lookup(oracle.as_ref(), self.rpc_client.clone(), address).await {
+                        Ok(pool_price) => {
+                            if let Some(price) = self.to_token_price(address.to_string(), oracle.name().to_string(), &pool_price, sol_usd) {
+                                all_prices.push(price);
+                            }
+                        }
+                        Err(e) => {
+                            debug!(dex = oracle.name(), mint = address, error = %e, "Price lookup failed");
+                            errors.record(oracle.name(), &e);
+                        }
                     }
                 }
 
                 if token_prices.len() >= 2 {
-                    price_map.insert(address.to_string(), token_prices);
+                    listed.push((address.to_string(), token_name, token_prices));
                 }
             }
             pb.inc(1);
         }
 
-        for (token_address, prices) in price_map {
-            if let (Some(raydium_price), Some(meteora_price)) = (
-                prices.get("Raydium"),
-                prices.get("Meteora")
-            ) {
-                let price_diff_percent = ((*raydium_price - *meteora_price).abs() / 
-                    (*meteora_price).min(*raydium_price)) * 100.0;
+        let epoch = match self.rpc_client.get_epoch() {
+            Ok(epoch) => epoch,
+            Err(e) => {
+                warn!(error = %e, "Failed to fetch epoch, transfer fees use the older schedule");
+                0
+            }
+        };
 
-                if price_diff_percent >= min_difference {
-                    let token_name = tokens_array.iter()
-                        .find(|t| t["address"].as_str() == Some(&token_address))
-                        .and_then(|t| t["name"].as_str().map(String::from));
+        for (token_address, token_name, prices) in listed {
+            // Read the mint at most once per token, and only for a candidate spread
+            let mut transfer_fee = None;
 
-                    opportunities.push(ArbitrageOpportunity::new(
+            for (i, a) in prices.iter().enumerate() {
+                for b in &prices[i + 1..] {
+                    if a.price <= 0.0 || b.price <= 0.0 {
+                        continue;
+                    }
+                    let price_diff_percent = (a.price - b.price).abs() / a.price.min(b.price) * 100.0;
+                    if price_diff_percent < min_difference {
+                        continue;
+                    }
+
+                    let transfer_fee = *transfer_fee.get_or_insert_with(|| self.fetch_transfer_fee(&token_address, epoch));
+                    let opportunity = ArbitrageOpportunity::new(
                         token_address.clone(),
-                        token_name,
-                        *raydium_price,
-                        *meteora_price
-                    ));
+                        token_name.clone(),
+                        (&a.dex_name, a.price),
+                        (&b.dex_name, b.price),
+                        self.quote_asset.mint().to_string(),
+                    ).with_transfer_fee(transfer_fee);
+
+                    // Fee-on-transfer tokens can eat the whole spread
+                    if opportunity.net_difference_percent < min_difference {
+                        continue;
+                    }
+
+                    let opportunity = if self.usd_view {
+                        opportunity.with_usd(a, b, self.trade_size_usd)
+                    } else {
+                        opportunity
+                    };
+                    opportunities.push(opportunity);
                 }
             }
         }
 
-        opportunities.sort_by(|a, b| b.price_difference_percent.partial_cmp(&a.price_difference_percent).unwrap());
+        opportunities.sort_by(|a, b| b.net_difference_percent.total_cmp(&a.net_difference_percent));
 
         pb.finish_with_message("Completed arbitrage analysis");
-        Ok(opportunities)
+        Ok(ScanResult {
+            prices: all_prices,
+            opportunities,
+            errors,
+            sol_usd,
+        })
     }
 
-    pub async fn save_arbitrage_opportunities(opportunities: &[ArbitrageOpportunity], file_path: &Path) -> Result<()> {
-        if let Some(parent) = file_path.parent() {
-            fs::create_dir_all(parent).context("Failed to create directory")?;
+    /// Compares every venue against the configured oracle feeds. Deviations at or
+    /// above `min_deviation` percent are reported, as are stale oracle prices.
+    pub async fn find_oracle_deviations(&self, min_deviation: f64) -> Result<Vec<OracleDeviation>> {
+        let oracle_dex = match &self.oracle {
+            Some(oracle_dex) => oracle_dex,
+            None => return Ok(Vec::new()),
+        };
+
+        let sol_usd = self.fetch_reference_price().await;
+        let usd_mint = mints::USDC.pubkey();
+        let mut deviations = Vec::new();
+
+        for feed in oracle_dex.feeds() {
+            let token_address = feed.mint.to_string();
+            let quote = match oracle_dex.get_oracle_quote(self.rpc_client.clone(), &token_address).await {
+                Ok(Some(quote)) => quote,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!(mint = %token_address, error = %e, "Failed to read oracle");
+                    continue;
+                }
+            };
+
+            let (oracle_price, oracle_confidence) = match (
+                self.quote_asset.convert(quote.price.price, &usd_mint, sol_usd),
+                self.quote_asset.convert(quote.price.conf, &usd_mint, sol_usd),
+            ) {
+                (Some(price), Some(conf)) if price > 0.0 => (price, conf),
+                _ => continue,
+            };
+
+            for dex in &self.dexes {
+                let dex_price = match lookup(dex.as_ref(), self.rpc_client.clone(), &token_address).await {
+                    Ok(pool_price) => match self.quote_asset.normalize(&pool_price, sol_usd) {
+                        Some(price) => price,
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+
+                let deviation_percent = (dex_price - oracle_price).abs() / oracle_price * 100.0;
+                if deviation_percent >= min_deviation || quote.stale {
+                    deviations.push(OracleDeviation {
+                        token_address: token_address.clone(),
+                        dex_name: dex.name().to_string(),
+                        dex_price,
+                        oracle_price,
+                        oracle_confidence,
+                        oracle_stale: quote.stale,
+                        quote_mint: self.quote_asset.mint().to_string(),
+                        deviation_percent,
+                        timestamp: chrono::Local::now(),
+                    });
+                }
+            }
         }
 
-        let mut file = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(file_path)
-            .context("Failed to open arbitrage log file")?;
-
-        for opp in opportunities {
-            let token_name = opp.token_name.as_deref().unwrap_or("Unknown");
-            let log_entry = format!(
-                "[{}] Token: {} ({})\n\tRaydium: {} SOL\n\tMeteora: {} SOL\n\tDifference: {:.2}%\n",
-                opp.timestamp.format("%Y-%m-%d %H:%M:%S"),
-                token_name,
-                opp.token_address,
-                opp.raydium_price,
-                opp.meteora_price,
-                opp.price_difference_percent
-            );
-            file.write_all(log_entry.as_bytes())?;
-            println!("{}", log_entry.trim());
+        deviations.sort_by(|a, b| b.deviation_percent.total_cmp(&a.deviation_percent));
+        Ok(deviations)
+    }
+
+    pub fn format_oracle_deviation(deviation: &OracleDeviation) -> String {
+        let quote_symbol = Self::quote_symbol(&deviation.quote_mint);
+        format!(
+            "[{}] {} on {}: {} {} vs oracle {} ± {} {}{}\n\tDeviation: {:.2}%\n",
+            deviation.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            deviation.token_address,
+            deviation.dex_name,
+            deviation.dex_price,
+            quote_symbol,
+            deviation.oracle_price,
+            deviation.oracle_confidence,
+            quote_symbol,
+            if deviation.oracle_stale { " (stale)" } else { "" },
+            deviation.deviation_percent
+        )
+    }
+
+    pub fn format_arbitrage_opportunity(opp: &ArbitrageOpportunity) -> String {
+        let token_name = opp.token_name.as_deref().unwrap_or("Unknown");
+        let quote_symbol = Self::quote_symbol(&opp.quote_mint);
+        let log_entry = format!(
+            "[{}] Token: {} ({})\n\tBuy on {}: {} {}\n\tSell on {}: {} {}\n\tDifference: {:.2}%\n",
+            opp.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            token_name,
+            opp.token_address,
+            opp.buy_venue,
+            opp.buy_price,
+            quote_symbol,
+            opp.sell_venue,
+            opp.sell_price,
+            quote_symbol,
+            opp.price_difference_percent
+        );
+        let log_entry = if opp.transfer_fee_bps > 0 {
+            format!(
+                "{}\tTransfer fee: {} bps\n\tNet difference: {:.2}%\n",
+                log_entry, opp.transfer_fee_bps, opp.net_difference_percent
+            )
+        } else {
+            log_entry
+        };
+        match opp.profit_usd {
+            Some(profit_usd) => format!(
+                "{}\tPrice: ${} USD\n\tLiquidity: {}\n\tEst. profit: ${:.2} USD\n",
+                log_entry,
+                opp.price_usd.unwrap_or_default(),
+                opp.liquidity_usd.map(|l| format!("${:.2} USD", l)).unwrap_or_else(|| "unknown".to_string()),
+                profit_usd
+            ),
+            None => log_entry,
         }
+    }
 
-        Ok(())
+    fn quote_symbol(quote_mint: &str) -> &'static str {
+        Pubkey::from_str(quote_mint)
+            .ok()
+            .and_then(|mint| QuoteAsset::from_mint(&mint))
+            .map(|quote| quote.symbol())
+            .unwrap_or("?")
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -0,0 +1,172 @@
+#[cfg(feature = "raydium")]
+use crate::dex::raydium::RaydiumDex;
+use crate::dex::PoolPrice;
+use crate::mints;
+use crate::oracle;
+use crate::rpc::Rpc;
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Asset every price is expressed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteAsset {
+    Sol,
+    Usd,
+}
+
+impl QuoteAsset {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            QuoteAsset::Sol => "SOL",
+            QuoteAsset::Usd => "USD",
+        }
+    }
+
+    pub fn mint(&self) -> Pubkey {
+        match self {
+            QuoteAsset::Sol => mints::WSOL.pubkey(),
+            QuoteAsset::Usd => mints::USDC.pubkey(),
+        }
+    }
+
+    pub fn from_mint(mint: &Pubkey) -> Option<Self> {
+        if *mint == mints::WSOL.pubkey() {
+            Some(QuoteAsset::Sol)
+        } else if mints::is_usd_stablecoin(mint) {
+            Some(QuoteAsset::Usd)
+        } else {
+            None
+        }
+    }
+
+    /// Converts an amount denominated in `from_mint` into this quote asset. `sol_usd`
+    /// is the reference price read in the same cycle; without a positive one only
+    /// same-quote amounts convert.
+    pub fn convert(&self, amount: f64, from_mint: &Pubkey, sol_usd: Option<f64>) -> Option<f64> {
+        let from_quote = Self::from_mint(from_mint)?;
+        let sol_usd = sol_usd.filter(|sol_usd| *sol_usd > 0.0);
+
+        match (from_quote, self) {
+            (QuoteAsset::Sol, QuoteAsset::Sol) | (QuoteAsset::Usd, QuoteAsset::Usd) => Some(amount),
+            (QuoteAsset::Sol, QuoteAsset::Usd) => sol_usd.map(|sol_usd| amount * sol_usd),
+            (QuoteAsset::Usd, QuoteAsset::Sol) => sol_usd.map(|sol_usd| amount / sol_usd),
+        }
+    }
+
+    pub fn normalize(&self, pool_price: &PoolPrice, sol_usd: Option<f64>) -> Option<f64> {
+        self.convert(pool_price.price, &pool_price.quote_mint, sol_usd)
+    }
+
+    pub fn normalize_liquidity(&self, pool_price: &PoolPrice, sol_usd: Option<f64>) -> Option<f64> {
+        pool_price
+            .liquidity
+            .and_then(|liquidity| self.convert(liquidity, &pool_price.quote_mint, sol_usd))
+    }
+}
+
+impl FromStr for QuoteAsset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "sol" => Ok(QuoteAsset::Sol),
+            "usd" | "usdc" => Ok(QuoteAsset::Usd),
+            _ => Err(anyhow!("Unknown quote asset: {}. Expected SOL or USD", s)),
+        }
+    }
+}
+
+#[cfg(feature = "raydium")]
+pub async fn fetch_sol_usd_price(rpc_client: Arc<dyn Rpc>, reference_pool: &Pubkey) -> Result<f64> {
+    // Base side is USDC (the non-native vault), quote side is SOL
+    let (usdc_amount, sol_amount, _) = RaydiumDex::get_pool_price(rpc_client, reference_pool).await?;
+
+    if sol_amount <= 0.0 {
+        return Err(anyhow!("Reference pool has no SOL liquidity"));
+    }
+
+    Ok(usdc_amount / sol_amount)
+}
+
+/// The reference pool is a Raydium AMM
+#[cfg(not(feature = "raydium"))]
+pub async fn fetch_sol_usd_price(_rpc_client: Arc<dyn Rpc>, _reference_pool: &Pubkey) -> Result<f64> {
+    Err(anyhow!("Reading the SOL/USD reference pool needs the raydium feature"))
+}
+
+/// Reads SOL/USD from the on-chain reference pool, falling back to a Pyth
+/// price account when the pool can't be read or none is configured
+pub async fn fetch_sol_usd_price_with_fallback(
+    rpc_client: Arc<dyn Rpc>,
+    reference_pool: Option<&Pubkey>,
+    fallback_oracle: Option<&Pubkey>,
+) -> Result<f64> {
+    let reference_price = match reference_pool {
+        Some(reference_pool) => fetch_sol_usd_price(rpc_client.clone(), reference_pool).await,
+        None => Err(anyhow!("No SOL/USD reference pool configured")),
+    };
+
+    match reference_price {
+        Ok(sol_usd) => Ok(sol_usd),
+        Err(pool_err) => match fallback_oracle {
+            Some(price_account) => oracle::fetch_pyth_price(rpc_client, price_account)
+                .await
+                .map_err(|oracle_err| anyhow!(
+                    "Reference pool failed ({}) and fallback oracle failed ({})",
+                    pool_err, oracle_err
+                )),
+            None => Err(pool_err),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_price(price: f64, quote_mint: Pubkey) -> PoolPrice {
+        PoolPrice {
+            price,
+            quote_mint,
+            liquidity: Some(price * 1000.0),
+        }
+    }
+
+    #[test]
+    fn converts_sol_to_usd() {
+        let price = pool_price(0.5, mints::WSOL.pubkey());
+
+        assert_eq!(QuoteAsset::Usd.normalize(&price, Some(150.0)), Some(75.0));
+        assert_eq!(QuoteAsset::Usd.normalize_liquidity(&price, Some(150.0)), Some(75_000.0));
+    }
+
+    #[test]
+    fn converts_usd_to_sol() {
+        assert_eq!(QuoteAsset::Sol.normalize(&pool_price(75.0, mints::USDC.pubkey()), Some(150.0)), Some(0.5));
+        assert_eq!(QuoteAsset::Sol.normalize(&pool_price(75.0, mints::USDT.pubkey()), Some(150.0)), Some(0.5));
+    }
+
+    #[test]
+    fn same_quote_needs_no_reference_price() {
+        assert_eq!(QuoteAsset::Sol.normalize(&pool_price(0.5, mints::WSOL.pubkey()), None), Some(0.5));
+        assert_eq!(QuoteAsset::Usd.normalize(&pool_price(75.0, mints::USDT.pubkey()), None), Some(75.0));
+    }
+
+    #[test]
+    fn cross_quote_without_a_reference_price_is_unpriced() {
+        assert_eq!(QuoteAsset::Usd.convert(0.5, &mints::WSOL.pubkey(), None), None);
+        assert_eq!(QuoteAsset::Sol.convert(75.0, &mints::USDC.pubkey(), None), None);
+        assert_eq!(QuoteAsset::Usd.convert(0.5, &mints::WSOL.pubkey(), Some(0.0)), None);
+        assert_eq!(QuoteAsset::Sol.convert(75.0, &mints::USDC.pubkey(), Some(0.0)), None);
+    }
+
+    #[test]
+    fn unknown_quote_mint_is_unpriced() {
+        let price = pool_price(2.0, mints::MSOL.pubkey());
+
+        assert_eq!(QuoteAsset::Sol.normalize(&price, Some(150.0)), None);
+        assert_eq!(QuoteAsset::Usd.normalize_liquidity(&price, Some(150.0)), None);
+    }
+}
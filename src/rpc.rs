@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Result of an [`Rpc`] call. The client error is boxed because it's a few
+/// hundred bytes and most calls succeed.
+pub type RpcResult<T> = std::result::Result<T, Box<ClientError>>;
+
+/// The RPC calls adapters make. [`RpcClient`] talks to a node,
+/// [`FixtureRpc`] replays recorded accounts and [`RecordingRpc`] records them.
+///
+/// ```no_run
+/// use raytx::cluster::Cluster;
+/// use raytx::dex::orca::OrcaDex;
+/// use raytx::rpc::{FixtureRpc, RecordingRpc};
+/// use raytx::{metrics, PriceFetcher};
+/// use std::sync::Arc;
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let orca = Arc::new(OrcaDex::new(Cluster::Mainnet.orca_program_id())?);
+///
+/// // Record what a lookup reads from the node
+/// let recorder = Arc::new(RecordingRpc::new(Arc::new(metrics::rpc_client(Cluster::Mainnet.rpc_url()))));
+/// let mut price_fetcher = PriceFetcher::new(recorder.clone());
+/// price_fetcher.add_dex(orca.clone());
+/// price_fetcher.fetch_token_prices("So11111111111111111111111111111111111111112").await?;
+/// recorder.save("sol.json")?;
+///
+/// // and price the same accounts again without one
+/// let mut price_fetcher = PriceFetcher::new(Arc::new(FixtureRpc::load("sol.json")?));
+/// price_fetcher.add_dex(orca);
+/// price_fetcher.fetch_token_prices("So11111111111111111111111111111111111111112").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Rpc: Send + Sync {
+    /// `None` when the account doesn't exist
+    fn get_account(&self, pubkey: &Pubkey) -> RpcResult<Option<Account>>;
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> RpcResult<Vec<Option<Account>>>;
+    /// Accounts owned by `program_id` that pass every filter
+    fn get_program_accounts(&self, program_id: &Pubkey, filters: Vec<RpcFilterType>) -> RpcResult<Vec<(Pubkey, Account)>>;
+    fn get_slot(&self) -> RpcResult<u64>;
+    fn get_epoch(&self) -> RpcResult<u64>;
+}
+
+impl Rpc for RpcClient {
+    fn get_account(&self, pubkey: &Pubkey) -> RpcResult<Option<Account>> {
+        Ok(self.get_account_with_commitment(pubkey, self.commitment())?.value)
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> RpcResult<Vec<Option<Account>>> {
+        Ok(RpcClient::get_multiple_accounts(self, pubkeys)?)
+    }
+
+    fn get_program_accounts(&self, program_id: &Pubkey, filters: Vec<RpcFilterType>) -> RpcResult<Vec<(Pubkey, Account)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        Ok(self.get_program_accounts_with_config(program_id, config)?)
+    }
+
+    fn get_slot(&self) -> RpcResult<u64> {
+        Ok(RpcClient::get_slot(self)?)
+    }
+
+    fn get_epoch(&self) -> RpcResult<u64> {
+        Ok(self.get_epoch_info()?.epoch)
+    }
+}
+
+/// Accounts and chain state as a node returned them, stored as JSON. Program
+/// account queries are answered from the recorded accounts, so a fixture
+/// replays any query whose matches it holds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fixture {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<u64>,
+    /// By address, in the node's JSON encoding; `null` records a missing account
+    pub accounts: BTreeMap<String, Option<UiAccount>>,
+}
+
+impl Fixture {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).with_context(|| format!("Failed to read fixture {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse fixture {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n").with_context(|| format!("Failed to write fixture {}", path.display()))
+    }
+
+    pub fn insert_account(&mut self, pubkey: &Pubkey, account: Option<&Account>) {
+        let account = account.map(|account| UiAccount::encode(pubkey, account, UiAccountEncoding::Base64, None, None));
+        self.accounts.insert(pubkey.to_string(), account);
+    }
+
+    fn account(&self, pubkey: &Pubkey) -> RpcResult<Option<Account>> {
+        match self.accounts.get(&pubkey.to_string()) {
+            Some(Some(account)) => account
+                .decode()
+                .map(Some)
+                .ok_or_else(|| fixture_error(format!("Fixture account {} can't be decoded", pubkey))),
+            Some(None) => Ok(None),
+            None => Err(fixture_error(format!("No fixture for account {}", pubkey))),
+        }
+    }
+}
+
+fn fixture_error(message: String) -> Box<ClientError> {
+    Box::new(ClientErrorKind::Custom(message).into())
+}
+
+/// Answers every call from a [`Fixture`]. Calls the fixture can't answer fail
+/// rather than guessing.
+pub struct FixtureRpc {
+    fixture: Fixture,
+}
+
+impl FixtureRpc {
+    pub fn new(fixture: Fixture) -> Self {
+        Self { fixture }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(Fixture::load(path)?))
+    }
+}
+
+impl Rpc for FixtureRpc {
+    fn get_account(&self, pubkey: &Pubkey) -> RpcResult<Option<Account>> {
+        self.fixture.account(pubkey)
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> RpcResult<Vec<Option<Account>>> {
+        pubkeys.iter().map(|pubkey| self.fixture.account(pubkey)).collect()
+    }
+
+    fn get_program_accounts(&self, program_id: &Pubkey, filters: Vec<RpcFilterType>) -> RpcResult<Vec<(Pubkey, Account)>> {
+        let mut accounts = Vec::new();
+        for (address, account) in &self.fixture.accounts {
+            let Some(account) = account else { continue };
+            if account.owner != program_id.to_string() {
+                continue;
+            }
+
+            let pubkey = Pubkey::from_str(address)
+                .map_err(|e| fixture_error(format!("Invalid fixture address {}: {}", address, e)))?;
+            let account: Account = account
+                .decode()
+                .ok_or_else(|| fixture_error(format!("Fixture account {} can't be decoded", address)))?;
+            let shared = AccountSharedData::from(account.clone());
+            if filters.iter().all(|filter| filter.allows(&shared)) {
+                accounts.push((pubkey, account));
+            }
+        }
+        Ok(accounts)
+    }
+
+    fn get_slot(&self) -> RpcResult<u64> {
+        self.fixture.slot.ok_or_else(|| fixture_error("No slot in fixture".to_string()))
+    }
+
+    fn get_epoch(&self) -> RpcResult<u64> {
+        self.fixture.epoch.ok_or_else(|| fixture_error("No epoch in fixture".to_string()))
+    }
+}
+
+/// Passes calls through to another [`Rpc`] and keeps every account and slot
+/// it returns, to be saved as a [`Fixture`]
+pub struct RecordingRpc {
+    inner: Arc<dyn Rpc>,
+    fixture: Mutex<Fixture>,
+}
+
+impl RecordingRpc {
+    pub fn new(inner: Arc<dyn Rpc>) -> Self {
+        Self {
+            inner,
+            fixture: Mutex::new(Fixture::default()),
+        }
+    }
+
+    /// Everything recorded so far
+    pub fn fixture(&self) -> Fixture {
+        self.fixture.lock().unwrap().clone()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.fixture().save(path)
+    }
+
+    fn record(&self, update: impl FnOnce(&mut Fixture)) {
+        update(&mut self.fixture.lock().unwrap());
+    }
+}
+
+impl Rpc for RecordingRpc {
+    fn get_account(&self, pubkey: &Pubkey) -> RpcResult<Option<Account>> {
+        let account = self.inner.get_account(pubkey)?;
+        self.record(|fixture| fixture.insert_account(pubkey, account.as_ref()));
+        Ok(account)
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> RpcResult<Vec<Option<Account>>> {
+        let accounts = self.inner.get_multiple_accounts(pubkeys)?;
+        self.record(|fixture| {
+            for (pubkey, account) in pubkeys.iter().zip(&accounts) {
+                fixture.insert_account(pubkey, account.as_ref());
+            }
+        });
+        Ok(accounts)
+    }
+
+    fn get_program_accounts(&self, program_id: &Pubkey, filters: Vec<RpcFilterType>) -> RpcResult<Vec<(Pubkey, Account)>> {
+        let accounts = self.inner.get_program_accounts(program_id, filters)?;
+        self.record(|fixture| {
+            for (pubkey, account) in &accounts {
+                fixture.insert_account(pubkey, Some(account));
+            }
+        });
+        Ok(accounts)
+    }
+
+    fn get_slot(&self) -> RpcResult<u64> {
+        let slot = self.inner.get_slot()?;
+        self.record(|fixture| fixture.slot = Some(slot));
+        Ok(slot)
+    }
+
+    fn get_epoch(&self) -> RpcResult<u64> {
+        let epoch = self.inner.get_epoch()?;
+        self.record(|fixture| fixture.epoch = Some(epoch));
+        Ok(epoch)
+    }
+}
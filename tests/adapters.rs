@@ -0,0 +1,198 @@
+// Adapter math against the accounts in tests/fixtures
+mod common;
+
+use common::{assert_close, replay};
+use raytx::dex::oracle::{OracleDex, OracleFeed};
+use raytx::dex::{lookup, DexError};
+use raytx::mints;
+use raytx::oracle::OracleKind;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+#[cfg(feature = "orca")]
+mod orca {
+    use super::*;
+    use raytx::cluster::Cluster;
+    use raytx::dex::orca::OrcaDex;
+    use raytx::PriceFetcher;
+    use std::sync::Arc;
+
+    // 6 decimals, as mint A in a deep whirlpool at 0.25 SOL and a shallow one
+    // at 0.3 SOL, and in a deeper still one against a mint that isn't a quote
+    const TOKEN: &str = "BNZisa39S8KLMDHmmU83FUAJgh2uz8pnzVqWqWyo4EcN";
+    const SHALLOW_POOL: &str = "GNcnHVQTbKUCzMHuh1G12TfLkPmCCbP1kgJwx4e4YnF4";
+    // 6 decimals, as mint B against WSOL at 2 tokens per SOL
+    const TOKEN_AS_B: &str = "BnmXtwuMFdp5UW2FEXbShY9MZ1yzpSjURWhTXbiNbsea";
+    const TOKEN_AS_B_POOL: &str = "CBYdCyUPV1RJCctEhxb3TLHfx2kkFgFvkesu2vevDV1w";
+
+    fn orca() -> OrcaDex {
+        OrcaDex::new(Cluster::Mainnet.orca_program_id()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn prices_the_deepest_whirlpool() {
+        let price = lookup(&orca(), replay("orca.json"), TOKEN).await.unwrap();
+
+        assert_close(price.price, 0.25);
+        assert_eq!(price.quote_mint, mints::WSOL.pubkey());
+        assert_eq!(price.liquidity, None);
+    }
+
+    #[tokio::test]
+    async fn inverts_a_whirlpool_holding_the_token_as_mint_b() {
+        let price = lookup(&orca(), replay("orca.json"), TOKEN_AS_B).await.unwrap();
+
+        assert_close(price.price, 0.5);
+        assert_eq!(price.quote_mint, mints::WSOL.pubkey());
+    }
+
+    #[tokio::test]
+    async fn decodes_a_whirlpool_as_b_per_a() {
+        let mut price_fetcher = PriceFetcher::new(replay("orca.json"));
+        price_fetcher.add_dex(Arc::new(orca()));
+
+        let pool = price_fetcher.decode_pool(&Pubkey::from_str(TOKEN_AS_B_POOL).unwrap()).await.unwrap();
+
+        assert_eq!(pool.base_mint, mints::WSOL.address);
+        assert_eq!(pool.quote_mint, TOKEN_AS_B);
+        assert_close(pool.price, 2.0);
+    }
+
+    #[tokio::test]
+    async fn decodes_a_whirlpool() {
+        let mut price_fetcher = PriceFetcher::new(replay("orca.json"));
+        price_fetcher.add_dex(Arc::new(orca()));
+
+        let pool = price_fetcher.decode_pool(&Pubkey::from_str(SHALLOW_POOL).unwrap()).await.unwrap();
+
+        assert_eq!(pool.dex_name, "Orca");
+        assert_eq!(pool.base_mint, TOKEN);
+        assert_eq!(pool.quote_mint, mints::WSOL.address);
+        assert_close(pool.price, 0.3);
+    }
+
+    #[tokio::test]
+    async fn token_without_whirlpool_is_not_found() {
+        let result = lookup(&orca(), replay("orca.json"), mints::USDC.address).await;
+
+        assert!(matches!(result, Err(DexError::PoolNotFound { .. })), "{:?}", result);
+    }
+}
+
+#[cfg(feature = "meteora")]
+#[tokio::test]
+async fn meteora_prices_from_reserves() {
+    use raytx::cluster::Cluster;
+    use raytx::dex::meteora::MeteoraDex;
+
+    // 9 decimals, 2,000 tokens against 3,000 USDC
+    const TOKEN: &str = "Wj8hRrUeXJY15sXPeCQzQGUAQvytVHSQL5K6FfwLGK3";
+
+    let meteora = MeteoraDex::new(Cluster::Mainnet.meteora_program_id()).unwrap();
+    let price = lookup(&meteora, replay("meteora.json"), TOKEN).await.unwrap();
+
+    assert_close(price.price, 1.5);
+    assert_eq!(price.quote_mint, mints::USDC.pubkey());
+    assert_close(price.liquidity.unwrap(), 6_000.0);
+}
+
+#[cfg(feature = "raydium")]
+#[tokio::test]
+async fn raydium_prices_from_vault_balances() {
+    use raytx::cluster::Cluster;
+    use raytx::dex::raydium::RaydiumDex;
+
+    // 6 decimals, 500,000 tokens against 250 SOL
+    const TOKEN: &str = "58GcDvt4PmPZCViE6TLJtBmjsJ5XFSoJYqp5hojdexMa";
+
+    let raydium = RaydiumDex::new(Cluster::Mainnet.raydium_program_id()).unwrap();
+    let price = lookup(&raydium, replay("raydium.json"), TOKEN).await.unwrap();
+
+    assert_close(price.price, 0.0005);
+    assert_eq!(price.quote_mint, mints::WSOL.pubkey());
+    assert_close(price.liquidity.unwrap(), 500.0);
+}
+
+#[cfg(feature = "raydium")]
+mod raydium_rpc_errors {
+    use super::*;
+    use raytx::cluster::Cluster;
+    use raytx::dex::raydium::RaydiumDex;
+    use raytx::rpc::{FixtureRpc, Rpc, RpcResult};
+    use solana_client::client_error::ClientErrorKind;
+    use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+    use solana_sdk::account::Account;
+    use std::sync::Arc;
+
+    // Fails the WSOL/token orientation of the pool search and answers the
+    // token/WSOL one from the fixture
+    struct HalfFailingRpc(Arc<FixtureRpc>);
+
+    impl Rpc for HalfFailingRpc {
+        fn get_account(&self, pubkey: &Pubkey) -> RpcResult<Option<Account>> {
+            self.0.get_account(pubkey)
+        }
+
+        fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> RpcResult<Vec<Option<Account>>> {
+            self.0.get_multiple_accounts(pubkeys)
+        }
+
+        fn get_program_accounts(&self, program_id: &Pubkey, filters: Vec<RpcFilterType>) -> RpcResult<Vec<(Pubkey, Account)>> {
+            let wsol_as_coin = RpcFilterType::Memcmp(Memcmp::new_base58_encoded(400, &mints::WSOL.pubkey().to_bytes()));
+            if filters.contains(&wsol_as_coin) {
+                return Err(Box::new(ClientErrorKind::Custom("connection reset".to_string()).into()));
+            }
+            self.0.get_program_accounts(program_id, filters)
+        }
+
+        fn get_slot(&self) -> RpcResult<u64> {
+            self.0.get_slot()
+        }
+
+        fn get_epoch(&self) -> RpcResult<u64> {
+            self.0.get_epoch()
+        }
+    }
+
+    // A failed orientation might have held the pool, so finding nothing in
+    // the other is an RPC error. The retries take two seconds.
+    #[tokio::test]
+    async fn half_failed_search_is_an_rpc_error() {
+        let raydium = RaydiumDex::new(Cluster::Mainnet.raydium_program_id()).unwrap();
+
+        let result = lookup(&raydium, Arc::new(HalfFailingRpc(replay("raydium.json"))), mints::USDC.address).await;
+        assert!(matches!(result, Err(DexError::RpcError(_))), "{:?}", result);
+
+        let result = lookup(&raydium, replay("raydium.json"), mints::USDC.address).await;
+        assert!(matches!(result, Err(DexError::PoolNotFound { .. })), "{:?}", result);
+    }
+}
+
+// $150 published 10 slots before the fixture's slot
+fn sol_usd_oracle() -> OracleDex {
+    OracleDex::new(vec![OracleFeed {
+        mint: mints::WSOL.pubkey(),
+        kind: OracleKind::Pyth,
+        account: Pubkey::from_str("ECGbN5GrrJXyyNZwk69qsku88ahqYe2TR3oBcXEthFeb").unwrap(),
+    }])
+}
+
+#[tokio::test]
+async fn oracle_quotes_pyth_price_in_usd() {
+    let price = lookup(&sol_usd_oracle(), replay("oracle.json"), mints::WSOL.address).await.unwrap();
+
+    assert_close(price.price, 150.0);
+    assert_eq!(price.quote_mint, mints::USDC.pubkey());
+}
+
+#[tokio::test]
+async fn oracle_rejects_stale_price() {
+    let oracle = sol_usd_oracle().with_max_staleness_slots(5);
+    let result = lookup(&oracle, replay("oracle.json"), mints::WSOL.address).await;
+
+    assert!(
+        matches!(result, Err(DexError::Stale { age_slots: 10, max_slots: 5 })),
+        "{:?}",
+        result
+    );
+}
@@ -0,0 +1,26 @@
+#![allow(dead_code)]
+
+use raytx::logging::{self, LogFormat};
+use raytx::rpc::FixtureRpc;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// `LOG_LEVEL=debug cargo test` shows what the adapters log
+#[ctor::ctor]
+fn init_logging() {
+    if let Ok(level) = std::env::var("LOG_LEVEL") {
+        logging::init(&level, LogFormat::Text).expect("Invalid LOG_LEVEL");
+    }
+}
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+pub fn replay(name: &str) -> Arc<FixtureRpc> {
+    Arc::new(FixtureRpc::load(fixture_path(name)).unwrap())
+}
+
+pub fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() <= expected.abs() * 1e-9, "{} != {}", actual, expected);
+}
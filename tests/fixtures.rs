@@ -0,0 +1,82 @@
+// Replaying and recording fixtures
+mod common;
+
+use common::replay;
+use raytx::cluster::Cluster;
+use raytx::dex::lookup;
+use raytx::dex::oracle::OracleDex;
+use raytx::metrics;
+use raytx::mints;
+use raytx::rpc::{FixtureRpc, RecordingRpc, Rpc};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const ORCA_TOKEN: &str = "BNZisa39S8KLMDHmmU83FUAJgh2uz8pnzVqWqWyo4EcN";
+
+#[test]
+fn unrecorded_account_is_an_error() {
+    let rpc = replay("orca.json");
+
+    let error = rpc.get_account(&mints::USDC.pubkey()).unwrap_err();
+    assert!(error.to_string().contains("No fixture for account"), "{}", error);
+    assert!(rpc.get_slot().is_err());
+}
+
+#[test]
+fn program_accounts_pass_the_filters() {
+    let rpc = replay("orca.json");
+    let program_id = Pubkey::from_str(Cluster::Mainnet.orca_program_id()).unwrap();
+    let token = Pubkey::from_str(ORCA_TOKEN).unwrap();
+
+    assert_eq!(rpc.get_program_accounts(&program_id, vec![]).unwrap().len(), 5);
+
+    let filters = vec![
+        RpcFilterType::DataSize(653),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(101, token.to_bytes().to_vec())),
+    ];
+    assert_eq!(rpc.get_program_accounts(&program_id, filters).unwrap().len(), 3);
+
+    assert!(rpc.get_program_accounts(&token, vec![]).unwrap().is_empty());
+}
+
+#[cfg(feature = "orca")]
+#[tokio::test]
+async fn recorded_lookup_replays() {
+    use common::assert_close;
+    use raytx::dex::orca::OrcaDex;
+
+    let orca = OrcaDex::new(Cluster::Mainnet.orca_program_id()).unwrap();
+    let recorder = Arc::new(RecordingRpc::new(replay("orca.json")));
+    let recorded = lookup(&orca, recorder.clone(), ORCA_TOKEN).await.unwrap();
+
+    // Only the token's three whirlpools and its mint were read
+    assert_eq!(recorder.fixture().accounts.len(), 4);
+
+    let path = std::env::temp_dir().join(format!("raytx-fixture-{}.json", std::process::id()));
+    recorder.save(&path).unwrap();
+    let replayed = lookup(&orca, Arc::new(FixtureRpc::load(&path).unwrap()), ORCA_TOKEN).await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert_close(replayed.unwrap().price, recorded.price);
+}
+
+// Records the SOL/USD oracle from a live node, `RPC_URL` or mainnet
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "slow_tests"), ignore = "needs a live RPC node, run with --features slow_tests")]
+async fn records_from_live_rpc() {
+    let url = std::env::var("RPC_URL").unwrap_or_else(|_| Cluster::Mainnet.rpc_url().to_string());
+    let recorder = Arc::new(RecordingRpc::new(Arc::new(metrics::rpc_client(&url))));
+    let oracle = OracleDex::new(vec![format!("{}:pyth:{}", mints::WSOL.address, Cluster::Mainnet.sol_usd_oracle())
+        .parse()
+        .unwrap()])
+    .with_max_staleness_slots(u64::MAX);
+
+    let live = lookup(&oracle, recorder.clone(), mints::WSOL.address).await.unwrap();
+    let fixture = recorder.fixture();
+    assert!(fixture.slot.is_some());
+
+    let replayed = lookup(&oracle, Arc::new(FixtureRpc::new(fixture)), mints::WSOL.address).await.unwrap();
+    assert_eq!(replayed.price, live.price);
+}
@@ -0,0 +1,139 @@
+// A full arbitrage pass over recorded Raydium and Meteora pools
+#![cfg(all(feature = "raydium", feature = "meteora"))]
+
+mod common;
+
+use common::{assert_close, replay};
+use raytx::cluster::Cluster;
+use raytx::dex::meteora::MeteoraDex;
+use raytx::dex::oracle::{OracleDex, OracleFeed};
+use raytx::dex::raydium::RaydiumDex;
+use raytx::oracle::OracleKind;
+use raytx::PriceFetcher;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// Token-2022 mint with a 1% transfer fee, at 0.0001 SOL on Raydium and
+// 0.000105 SOL on Meteora
+const TOKEN: &str = "7KWyXQPYXZuAvrrkQokGVopgkdKaGhbMQDcpfvZ75NFH";
+
+// Pyth account publishing $150
+const PYTH_ACCOUNT: &str = "ECGbN5GrrJXyyNZwk69qsku88ahqYe2TR3oBcXEthFeb";
+
+// Raydium SOL/USDC AMM in scan_usd.json holding 150,000 USDC against 1,000 SOL
+const SOL_USDC_POOL: &str = "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2";
+
+fn price_fetcher() -> PriceFetcher {
+    let mut price_fetcher = PriceFetcher::new(replay("scan.json"));
+    price_fetcher.add_dex(Arc::new(RaydiumDex::new(Cluster::Mainnet.raydium_program_id()).unwrap()));
+    price_fetcher.add_dex(Arc::new(MeteoraDex::new(Cluster::Mainnet.meteora_program_id()).unwrap()));
+    price_fetcher.set_token_sources("", vec![TOKEN.to_string()]);
+    price_fetcher
+}
+
+fn reversed_price_fetcher() -> PriceFetcher {
+    let mut price_fetcher = PriceFetcher::new(replay("scan.json"));
+    price_fetcher.add_dex(Arc::new(MeteoraDex::new(Cluster::Mainnet.meteora_program_id()).unwrap()));
+    price_fetcher.add_dex(Arc::new(RaydiumDex::new(Cluster::Mainnet.raydium_program_id()).unwrap()));
+    price_fetcher.set_token_sources("", vec![TOKEN.to_string()]);
+    price_fetcher
+}
+
+#[tokio::test]
+async fn finds_spread_net_of_transfer_fee() {
+    let scan = price_fetcher().scan(1.0).await.unwrap();
+
+    assert_eq!(scan.prices.len(), 2);
+    assert_eq!(scan.opportunities.len(), 1);
+
+    let opportunity = &scan.opportunities[0];
+    assert_eq!(opportunity.token_address, TOKEN);
+    assert_close(opportunity.buy_price, 0.0001);
+    assert_close(opportunity.sell_price, 0.000105);
+    assert_close(opportunity.price_difference_percent, 5.0);
+    assert_eq!(opportunity.transfer_fee_bps, 100);
+    // Sold for 0.99² of the Meteora price
+    assert_close(opportunity.net_difference_percent, 2.9105);
+
+    let ((buy_dex, _), (sell_dex, _)) = opportunity.venues();
+    assert_eq!((buy_dex, sell_dex), ("Raydium", "Meteora"));
+}
+
+#[tokio::test]
+async fn transfer_fee_can_eat_the_spread() {
+    let scan = price_fetcher().scan(3.0).await.unwrap();
+
+    assert_eq!(scan.prices.len(), 2);
+    assert!(scan.opportunities.is_empty());
+}
+
+#[tokio::test]
+async fn direction_does_not_depend_on_registration_order() {
+    let scan = reversed_price_fetcher().scan(1.0).await.unwrap();
+
+    assert_eq!(scan.opportunities.len(), 1);
+    let ((buy_dex, buy_price), (sell_dex, sell_price)) = scan.opportunities[0].venues();
+    assert_eq!((buy_dex, sell_dex), ("Raydium", "Meteora"));
+    assert_close(buy_price, 0.0001);
+    assert_close(sell_price, 0.000105);
+}
+
+#[tokio::test]
+async fn oracle_is_priced_but_never_traded() {
+    let pyth_account = Pubkey::from_str(PYTH_ACCOUNT).unwrap();
+    let mut price_fetcher = price_fetcher();
+    price_fetcher.set_oracle(OracleDex::new(vec![OracleFeed {
+        mint: Pubkey::from_str(TOKEN).unwrap(),
+        kind: OracleKind::Pyth,
+        account: pyth_account,
+    }]));
+    // SOL/USD from the same account, so the oracle's $150 is 1 SOL
+    price_fetcher.set_usd_view(false, None, Some(pyth_account), 1000.0);
+
+    let scan = price_fetcher.scan(1.0).await.unwrap();
+
+    let oracle = scan.prices.iter().find(|price| price.dex_name == "Oracle").unwrap();
+    assert_close(oracle.price, 1.0);
+    assert_eq!(scan.opportunities.len(), 1);
+    assert!(
+        scan.opportunities.iter().all(|opportunity| opportunity.buy_venue != "Oracle" && opportunity.sell_venue != "Oracle"),
+        "{:?}",
+        scan.opportunities
+    );
+    assert_eq!(serde_json::to_value(&scan.errors).unwrap(), serde_json::json!({}));
+}
+
+#[tokio::test]
+async fn oracle_without_a_feed_is_not_looked_up() {
+    let mut price_fetcher = price_fetcher();
+    price_fetcher.set_oracle(OracleDex::new(Vec::new()));
+
+    let scan = price_fetcher.scan(1.0).await.unwrap();
+
+    assert_eq!(scan.prices.len(), 2);
+    assert_eq!(serde_json::to_value(&scan.errors).unwrap(), serde_json::json!({}));
+}
+
+#[tokio::test]
+async fn converts_a_usdc_quote_through_the_reference_pool() {
+    // Same pools, but Meteora quotes $0.0165 in USDC and the reference pool is at $150
+    let mut price_fetcher = PriceFetcher::new(replay("scan_usd.json"));
+    price_fetcher.add_dex(Arc::new(RaydiumDex::new(Cluster::Mainnet.raydium_program_id()).unwrap()));
+    price_fetcher.add_dex(Arc::new(MeteoraDex::new(Cluster::Mainnet.meteora_program_id()).unwrap()));
+    price_fetcher.set_token_sources("", vec![TOKEN.to_string()]);
+    price_fetcher.set_usd_view(false, Some(Pubkey::from_str(SOL_USDC_POOL).unwrap()), None, 1000.0);
+
+    let scan = price_fetcher.scan(1.0).await.unwrap();
+
+    assert_close(scan.sol_usd.unwrap(), 150.0);
+    assert_eq!(scan.opportunities.len(), 1);
+
+    let opportunity = &scan.opportunities[0];
+    let ((buy_dex, buy_price), (sell_dex, sell_price)) = opportunity.venues();
+    assert_eq!((buy_dex, sell_dex), ("Raydium", "Meteora"));
+    assert_close(buy_price, 0.0001);
+    assert_close(sell_price, 0.00011);
+    assert_close(opportunity.price_difference_percent, 10.0);
+    assert_close(opportunity.net_difference_percent, 7.811);
+}